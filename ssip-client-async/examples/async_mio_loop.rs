@@ -30,7 +30,7 @@ fn main() -> ClientResult<()> {
     let mut ssip_client = QueuedClient::new(fifo::Builder::new().build()?);
     let speech_input_token = Token(1);
     let speech_output_token = Token(2);
-    ssip_client.register(&poll, speech_input_token, speech_output_token)?;
+    ssip_client.register_with(poll.registry(), speech_input_token, speech_output_token)?;
 
     // Loop for events
     let mut events = Events::with_capacity(16);
@@ -1,8 +1,7 @@
 use ssip_client_async::{
     fifo::asynchronous_tokio::Builder,
-    types::{ClientName, ClientResult, ClientScope},
+    types::{ClientName, ClientResult, ClientScope, Volume},
 };
-use tokio;
 
 #[cfg(all(unix, feature = "tokio"))]
 #[tokio::main(flavor = "current_thread")]
@@ -29,7 +28,7 @@ async fn main() -> ClientResult<()> {
     let volume = client.get_volume().await?.receive_u8().await?;
     println!("volume: {}", volume);
     match client
-        .set_volume(ClientScope::Current, 1)
+        .set_volume(ClientScope::Current, Volume::try_from(1)?)
         .await?
         .receive()
         .await
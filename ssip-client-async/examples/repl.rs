@@ -0,0 +1,123 @@
+// Send SSIP commands typed on the standard input and print the answers and events.
+//
+// After `SPEAK`, the lines typed are the text of the message, up to a line with a single dot.
+
+#[cfg(all(unix, not(feature = "async-mio")))]
+use ssip_client_async::{
+    client::Source, fifo, Client, ClientError, ClientName, ClientResult, Event, NotificationType,
+    ReturnCode, OK_BYE, OK_RECEIVING_DATA,
+};
+#[cfg(all(unix, not(feature = "async-mio")))]
+use std::{
+    io::{self, Read, Write},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn main() -> ClientResult<()> {
+    let mut client = fifo::Builder::new().build()?;
+    client
+        .set_client_name(ClientName::new("joe", "repl"))?
+        .check_client_name_set()?
+        .set_notification_checked(NotificationType::All, true)?;
+
+    // Lines are read in a thread to print the events while waiting for input.
+    let (sender, input) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut text: Option<Vec<String>> = None;
+    loop {
+        let line = match input.try_recv() {
+            Ok(line) => line?,
+            Err(mpsc::TryRecvError::Empty) => {
+                // Wait a little for an event before checking the input again.
+                client
+                    .get_ref()
+                    .set_read_timeout(Some(Duration::from_millis(100)))?;
+                let result = client.receive_event();
+                client.get_ref().set_read_timeout(None)?;
+                match result {
+                    Ok(event) => print_event(&event),
+                    Err(ClientError::NotReady | ClientError::Timeout) => (),
+                    Err(ClientError::Io(err))
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                        ) => {}
+                    Err(err) => return Err(err),
+                }
+                continue;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => break,
+        };
+        match text.as_mut() {
+            Some(lines) if line == "." => {
+                client.send_lines(lines)?;
+                text = None;
+                let result = client.receive_message_id();
+                print_events(&mut client);
+                match result {
+                    Ok(id) => println!("message {}", id),
+                    Err(err) => println!("error: {}", err),
+                }
+            }
+            Some(lines) => lines.push(line),
+            None => match client.execute_raw(&line) {
+                Ok(answer) => {
+                    print_events(&mut client);
+                    for line in answer.iter() {
+                        println!("{}", line);
+                    }
+                    // The status line is the last one, it starts with the code.
+                    let code = answer
+                        .last()
+                        .and_then(|status| status.get(..3)?.parse::<ReturnCode>().ok());
+                    match code {
+                        Some(OK_RECEIVING_DATA) => text = Some(Vec::new()),
+                        Some(OK_BYE) => {
+                            client.forget_quit();
+                            break;
+                        }
+                        _ => (),
+                    }
+                }
+                Err(err) => println!("error: {}", err),
+            },
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn print_event(event: &Event) {
+    println!(
+        "event {}: message {} client {}",
+        event.ntype, event.id.message, event.id.client
+    );
+}
+
+/// Print the events received while waiting for an answer.
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn print_events<S: Read + Write + Source>(client: &mut Client<S>) {
+    for event in client.drain_events() {
+        print_event(&event);
+    }
+}
+
+#[cfg(all(unix, feature = "async-mio"))]
+fn main() {
+    println!("see async_mio_loop for an example of asynchronous client.");
+}
+
+#[cfg(not(unix))]
+fn main() {
+    println!("example only available on unix.");
+}
@@ -0,0 +1,173 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::types::*;
+
+/// Core commands of the asynchronous clients, whatever the runtime.
+///
+/// This is the recommended abstraction for libraries that must not depend on a runtime:
+/// take a client bounded by this trait rather than a concrete client. It's implemented
+/// by [`tokio::AsyncClient`](crate::tokio::AsyncClient) and by
+/// [`async_std::AsyncClient`](crate::async_std::AsyncClient), which is also the client
+/// of the async-io backend. The `mio` API is not asynchronous and doesn't implement it.
+/// With the `mock` feature, [`MockClient`](crate::mock::MockClient) implements it to test
+/// code without a server. The synchronous [`Client`](crate::Client) implements the same
+/// commands with [`SsipClient`](crate::client::SsipClient).
+///
+/// Unlike the methods of the clients, the commands check the status of the answer.
+/// The returned futures are not bound to be `Send`.
+///
+/// ```no_run
+/// use ssip_client_async::{AsyncSsipClient, ClientResult, ClientScope, MessageId, Rate};
+///
+/// async fn say_slowly<C: AsyncSsipClient>(client: &mut C, text: &str) -> ClientResult<MessageId> {
+///     client.set_rate(ClientScope::Current, Rate::try_from(-50).unwrap()).await?;
+///     client.say_line(text).await
+/// }
+/// ```
+#[allow(async_fn_in_trait)]
+pub trait AsyncSsipClient {
+    /// Set the client name and check the answer.
+    async fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<()>;
+
+    /// Speak a text and return the id of the queued message.
+    ///
+    /// Lines starting with a dot are escaped.
+    async fn say_line(&mut self, text: &str) -> ClientResult<MessageId>;
+
+    /// Set the rate of speech.
+    async fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<()>;
+
+    /// Set the pitch of the voice.
+    async fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<()>;
+
+    /// Set the volume of the voice.
+    async fn set_volume(&mut self, scope: ClientScope, value: Volume) -> ClientResult<()>;
+
+    /// Set the priority of the next messages.
+    async fn set_priority(&mut self, priority: Priority) -> ClientResult<()>;
+
+    /// Stop the messages.
+    async fn stop(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Cancel the messages.
+    async fn cancel(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Pause the messages.
+    async fn pause(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Resume the messages.
+    async fn resume(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Receive the next notification.
+    async fn receive_event(&mut self) -> ClientResult<Event>;
+
+    /// Close the connection.
+    async fn quit(&mut self) -> ClientResult<()>;
+}
+
+/// Implement [`AsyncSsipClient`] for the `AsyncClient` in scope.
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+macro_rules! impl_async_ssip_client {
+    () => {
+        impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> $crate::async_client::AsyncSsipClient
+            for AsyncClient<R, W>
+        {
+            async fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<()> {
+                AsyncClient::set_client_name(self, client_name)
+                    .await?
+                    .check_client_name_set()
+                    .await?;
+                Ok(())
+            }
+
+            async fn say_line(&mut self, text: &str) -> ClientResult<MessageId> {
+                self.speak().await?.check_receiving_data().await?;
+                self.send_text(text).await?;
+                self.receive_message_id().await
+            }
+
+            async fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<()> {
+                AsyncClient::set_rate(self, scope, value)
+                    .await?
+                    .check_status(OK_RATE_SET)
+                    .await?;
+                Ok(())
+            }
+
+            async fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<()> {
+                AsyncClient::set_pitch(self, scope, value)
+                    .await?
+                    .check_status(OK_PITCH_SET)
+                    .await?;
+                Ok(())
+            }
+
+            async fn set_volume(&mut self, scope: ClientScope, value: Volume) -> ClientResult<()> {
+                AsyncClient::set_volume(self, scope, value)
+                    .await?
+                    .check_status(OK_VOLUME_SET)
+                    .await?;
+                Ok(())
+            }
+
+            async fn set_priority(&mut self, priority: Priority) -> ClientResult<()> {
+                AsyncClient::set_priority(self, priority)
+                    .await?
+                    .check_status(OK_PRIORITY_SET)
+                    .await?;
+                Ok(())
+            }
+
+            async fn stop(&mut self, scope: MessageScope) -> ClientResult<()> {
+                AsyncClient::stop(self, scope)
+                    .await?
+                    .check_status(OK_STOPPED)
+                    .await?;
+                Ok(())
+            }
+
+            async fn cancel(&mut self, scope: MessageScope) -> ClientResult<()> {
+                AsyncClient::cancel(self, scope)
+                    .await?
+                    .check_status(OK_CANCELED)
+                    .await?;
+                Ok(())
+            }
+
+            async fn pause(&mut self, scope: MessageScope) -> ClientResult<()> {
+                AsyncClient::pause(self, scope)
+                    .await?
+                    .check_status(OK_PAUSED)
+                    .await?;
+                Ok(())
+            }
+
+            async fn resume(&mut self, scope: MessageScope) -> ClientResult<()> {
+                AsyncClient::resume(self, scope)
+                    .await?
+                    .check_status(OK_RESUMED)
+                    .await?;
+                Ok(())
+            }
+
+            async fn receive_event(&mut self) -> ClientResult<Event> {
+                AsyncClient::receive_event(self).await
+            }
+
+            async fn quit(&mut self) -> ClientResult<()> {
+                AsyncClient::quit(self).await?.check_status(OK_BYE).await?;
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+pub(crate) use impl_async_ssip_client;
@@ -0,0 +1,38 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Client for the [async-io](https://crates.io/crates/async-io) reactor used by `smol`.
+//!
+//! The client is the runtime-agnostic [`AsyncClient`] on a unix socket registered in
+//! the async-io reactor, so it has the same commands as the async-std client. It's built on
+//! the `futures-io` traits and doesn't depend on async-std.
+//!
+//! ```no_run
+//! use ssip_client_async::{fifo::asynchronous_async_io::Builder, ClientName, OK_CLIENT_NAME_SET};
+//! smol::block_on(async {
+//!     let mut client = Builder::default().build().await?;
+//!     client
+//!         .set_client_name(ClientName::new("joe", "hello"))
+//!         .await?
+//!         .check_status(OK_CLIENT_NAME_SET)
+//!         .await?;
+//!     Ok::<(), ssip_client_async::ClientError>(())
+//! })?;
+//! # Ok::<(), ssip_client_async::ClientError>(())
+//! ```
+
+use std::os::unix::net::UnixStream;
+
+use async_io::Async;
+use futures_util::io::{BufReader, BufWriter};
+
+pub use crate::async_std::AsyncClient;
+
+/// Client on a unix socket for the async-io reactor.
+pub type AsyncIoClient = AsyncClient<BufReader<Async<UnixStream>>, BufWriter<Async<UnixStream>>>;
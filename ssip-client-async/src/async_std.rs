@@ -7,14 +7,23 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use std::path::PathBuf;
+use std::time::Duration;
+#[cfg(not(feature = "async-io"))]
+use std::time::Instant;
+
 use crate::constants::*;
+use crate::keepalive::{probe_result, Keepalive};
+use crate::parse::{
+    parse_client_id, parse_debug_path, parse_event, parse_event_id, parse_handshake,
+    parse_single_integer, parse_single_value, parse_typed_lines,
+};
 use crate::protocol::{
-    flush_lines_async_std, parse_event_id, parse_single_integer, parse_single_value,
-    parse_typed_lines, write_lines_async_std,
+    flush_data_block_async_std, flush_lines_async_std, text_lines, PartialAnswer,
 };
 use crate::types::*;
 
-use async_std::io::{BufRead as AsyncBufRead, Write as AsyncWrite};
+use futures_util::io::{AsyncBufRead, AsyncWrite};
 
 /// Convert boolean to ON or OFF
 fn on_off(value: bool) -> &'static str {
@@ -43,48 +52,124 @@ macro_rules! send_toggle {
     };
 }
 
-macro_rules! send_range {
-    ($output:expr, $fmt:expr, $scope:expr, $val:expr) => {
-        send_one_line!(
-            $output,
-            $fmt,
-            $scope,
-            std::cmp::max(-100, std::cmp::min(100, $val))
-        )
-    };
-}
-
 /// SSIP client on generic async stream
 ///
 /// There are two ways to send requests and receive responses:
 /// * Either with the generic [`AsyncClient::send`] and [`AsyncClient::receive`]
 /// * Or with the specific methods such as [`AsyncClient::set_rate`], ..., [`AsyncClient::get_rate`], ...
+///
+/// The client is `Send` and `Sync` when the reader and the writer are, so it can be
+/// used in tasks spawned on a multi-threaded runtime.
 pub struct AsyncClient<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> {
     input: R,
     output: W,
+    partial: PartialAnswer,
+    client_id: Option<ClientId>,
+    handshake: Option<Handshake>,
+    keepalive: Keepalive,
 }
 impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
     pub fn new(input: R, output: W) -> Self {
-        Self { input, output }
+        Self {
+            input,
+            output,
+            partial: PartialAnswer::default(),
+            client_id: None,
+            handshake: None,
+            keepalive: Keepalive::default(),
+        }
+    }
+    /// Accept answers whose lines end with a bare LF instead of CRLF.
+    ///
+    /// By default, the client is strict and such lines are rejected as invalid data.
+    /// Requests are always written with CRLF.
+    pub fn set_lenient_line_endings(&mut self, lenient: bool) -> &mut Self {
+        self.partial.set_lenient(lenient);
+        self
+    }
+
+    /// Client id assigned by the server, if it was sent in the answer to [`AsyncClient::set_client_name`].
+    ///
+    /// It can be used to target this connection with [`ClientScope::Client`].
+    pub fn client_id(&self) -> Option<&ClientId> {
+        self.client_id.as_ref()
+    }
+
+    /// Answer to the last successful `SET self CLIENT_NAME`, if it was checked.
+    pub fn handshake(&self) -> Option<&Handshake> {
+        self.handshake.as_ref()
+    }
+
+    /// Enable or disable the keepalive of an idle connection.
+    ///
+    /// Same as [`crate::Client::set_keepalive`]. The client doesn't spawn a task: the probe is
+    /// scheduled on a timer of the reactor by [`AsyncClient::wait_keepalive`].
+    pub fn set_keepalive(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.keepalive.set_interval(interval);
+        self
+    }
+
+    /// Probe the connection if the keepalive interval has elapsed without requests.
+    ///
+    /// No probe is sent while replies to requests sent with [`AsyncClient::send`] are not
+    /// received yet, since the probe's answer would be mistaken for one of them.
+    ///
+    /// Return true if `GET RATE` was sent. A lost connection is reported as
+    /// [`ClientError::Disconnected`].
+    pub async fn keepalive(&mut self) -> ClientResult<bool> {
+        if !self.keepalive.is_due() {
+            return Ok(false);
+        }
+        let mut lines = Vec::new();
+        let result = match self.send(Request::GetRate).await {
+            Ok(client) => client.receive_answer(&mut lines).await,
+            Err(err) => Err(err),
+        };
+        probe_result(result).map(|_| true)
+    }
+
+    /// Wait on a timer until the keepalive is due, then probe the connection.
+    ///
+    /// It's meant to be raced with the futures sending the requests, for example with
+    /// `futures::select!`, since each request postpones the next probe. Dropping the future
+    /// while waiting sends nothing. The future never completes while the keepalive is
+    /// disabled or a reply is awaited.
+    pub async fn wait_keepalive(&mut self) -> ClientResult<()> {
+        match self.keepalive.next_probe() {
+            #[cfg(feature = "async-io")]
+            Some(deadline) => {
+                async_io::Timer::at(deadline).await;
+            }
+            #[cfg(not(feature = "async-io"))]
+            Some(deadline) => {
+                ::async_std::task::sleep(deadline.saturating_duration_since(Instant::now())).await
+            }
+            None => std::future::pending().await,
+        }
+        self.keepalive().await.map(|_| ())
     }
     /// Send lines of text (terminated by a single dot).
+    ///
+    /// Lines are escaped as in [`encode_data_block`](crate::encode_data_block).
     pub async fn send_lines(&mut self, lines: &[String]) -> ClientResult<()> {
-        const END_OF_DATA: [&str; 1] = ["."];
-        write_lines_async_std(
-            &mut self.output,
-            lines
-                .iter()
-                .map(|s| s.as_str())
-                .collect::<Vec<&str>>()
-                .as_slice(),
-        )
-        .await?;
-        flush_lines_async_std(&mut self.output, &END_OF_DATA).await?;
-        Ok(())
+        self.keepalive.request_sent();
+        flush_data_block_async_std(&mut self.output, lines.iter().map(String::as_str)).await
+    }
+    /// Send a text as data lines, escaped with [`encode_data_block`](crate::encode_data_block).
+    pub(crate) async fn send_text(&mut self, text: &str) -> ClientResult<()> {
+        self.keepalive.request_sent();
+        flush_data_block_async_std(&mut self.output, text_lines(text)).await
     }
     /// Receive answer from server
     async fn receive_answer(&mut self, lines: &mut Vec<String>) -> ClientStatus {
-        crate::protocol::receive_answer_async_std(&mut self.input, Some(lines)).await
+        let answer = crate::protocol::receive_answer_async_std(
+            &mut self.input,
+            &mut self.partial,
+            Some(lines),
+        )
+        .await;
+        self.keepalive.answer_received(&answer);
+        answer
     }
     /// Receive one response.
     pub async fn receive(&mut self) -> ClientResult<Response> {
@@ -149,12 +234,18 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
             OK_OUTSIDE_BLOCK => Ok(Response::OutsideBlock),
             OK_NOT_IMPLEMENTED => Ok(Response::NotImplemented),
             EVENT_INDEX_MARK => match lines.len() {
-                0..=2 => Err(ClientError::TooFewLines),
+                n @ 0..=2 => Err(ClientError::TooFewLines {
+                    expected: 3,
+                    got: n,
+                }),
                 3 => Ok(Response::EventIndexMark(
                     parse_event_id(&lines)?,
                     lines[2].to_owned(),
                 )),
-                _ => Err(ClientError::TooManyLines),
+                n => Err(ClientError::TooManyLines {
+                    expected: 3,
+                    got: n,
+                }),
             },
             EVENT_BEGIN => Ok(Response::EventBegin(parse_event_id(&lines)?)),
             EVENT_END => Ok(Response::EventEnd(parse_event_id(&lines)?)),
@@ -167,16 +258,22 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
 
     /// Send a request
     pub async fn send(&mut self, request: Request) -> ClientResult<&mut Self> {
+        self.keepalive.request_sent();
         match request {
-            Request::SetName(client_name) => send_one_line!(
-                self,
-                "SET self CLIENT_NAME {}:{}:{}",
-                client_name.user,
-                client_name.application,
-                client_name.component
-            ),
+            Request::SetName(client_name) => {
+                client_name.validate()?;
+                send_one_line!(
+                    self,
+                    "SET self CLIENT_NAME {}:{}:{}",
+                    client_name.user,
+                    client_name.application,
+                    client_name.component
+                )
+            }
             Request::Speak => send_one_line!(self, "SPEAK"),
-            Request::SendLine(line) => send_one_line!(self, &line),
+            Request::SendLine(line) => {
+                flush_data_block_async_std(&mut self.output, [line.as_str()]).await
+            }
             Request::SendLines(lines) => self.send_lines(&lines).await,
             Request::SpeakChar(ch) => send_one_line!(self, "CHAR {}", ch),
             Request::SpeakKey(key) => send_one_line!(self, "KEY {}", key),
@@ -184,6 +281,10 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
             Request::Cancel(scope) => send_one_line!(self, "CANCEL {}", scope),
             Request::Pause(scope) => send_one_line!(self, "PAUSE {}", scope),
             Request::Resume(scope) => send_one_line!(self, "RESUME {}", scope),
+            Request::StopClient(scope) => send_one_line!(self, "STOP {}", scope),
+            Request::CancelClient(scope) => send_one_line!(self, "CANCEL {}", scope),
+            Request::PauseClient(scope) => send_one_line!(self, "PAUSE {}", scope),
+            Request::ResumeClient(scope) => send_one_line!(self, "RESUME {}", scope),
             Request::SetPriority(prio) => send_one_line!(self, "SET self PRIORITY {}", prio),
             Request::SetDebug(value) => send_toggle!(self, "SET all DEBUG {}", value),
             Request::SetOutputModule(scope, value) => {
@@ -199,6 +300,7 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
             Request::SetPunctuationMode(scope, mode) => {
                 send_one_line!(self, "SET {} PUNCTUATION {}", scope, mode)
             }
+            Request::GetPunctuationMode => send_one_line!(self, "GET PUNCTUATION"),
             Request::SetSpelling(scope, value) => {
                 send_toggle!(self, "SET {} SPELLING {}", scope, value)
             }
@@ -214,12 +316,18 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
                 send_one_line!(self, "SET {} SYNTHESIS_VOICE {}", scope, value)
             }
             Request::ListSynthesisVoices => send_one_line!(self, "LIST SYNTHESIS_VOICES"),
-            Request::SetRate(scope, value) => send_range!(self, "SET {} RATE {}", scope, value),
+            Request::SetRate(scope, value) => send_one_line!(self, "SET {} RATE {}", scope, value),
             Request::GetRate => send_one_line!(self, "GET RATE"),
-            Request::SetPitch(scope, value) => send_range!(self, "SET {} PITCH {}", scope, value),
+            Request::SetPitch(scope, value) => {
+                send_one_line!(self, "SET {} PITCH {}", scope, value)
+            }
             Request::GetPitch => send_one_line!(self, "GET PITCH"),
+            Request::SetPitchRange(scope, value) => {
+                send_one_line!(self, "SET {} PITCH_RANGE {}", scope, value)
+            }
+            Request::GetPitchRange => send_one_line!(self, "GET PITCH_RANGE"),
             Request::SetVolume(scope, value) => {
-                send_range!(self, "SET {} VOLUME {}", scope, value)
+                send_one_line!(self, "SET {} VOLUME {}", scope, value)
             }
             Request::GetVolume => send_one_line!(self, "GET VOLUME"),
             Request::SetPauseContext(scope, value) => {
@@ -272,7 +380,12 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
             Request::HistorySearch(scope, condition) => {
                 send_one_line!(self, "HISTORY SEARCH {} \"{}\"", scope, condition)
             }
+            Request::Help => send_one_line!(self, "HELP"),
             Request::Quit => send_one_line!(self, "QUIT"),
+            _ => Err(ClientError::io_error(
+                std::io::ErrorKind::Unsupported,
+                "request not supported by this client",
+            )),
         }?;
         Ok(self)
     }
@@ -321,9 +434,21 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
         self.send(Request::SetPriority(prio)).await
     }
 
-    /// Set debug mode. Return the log location
-    pub async fn set_debug(&mut self, value: bool) -> ClientResult<&mut Self> {
-        self.send(Request::SetDebug(value)).await
+    /// Set debug mode.
+    ///
+    /// When enabling, return the directory where the server writes its debug logs.
+    /// When disabling, return `None`.
+    pub async fn set_debug(&mut self, value: bool) -> ClientResult<Option<PathBuf>> {
+        self.send(Request::SetDebug(value)).await?;
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines).await?;
+        if status.code != OK_DEBUG_SET {
+            Err(ClientError::UnexpectedStatus(status.code))
+        } else if value {
+            Ok(parse_debug_path(&status, &lines))
+        } else {
+            Ok(None)
+        }
     }
 
     /// Set output module
@@ -429,8 +554,8 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
         self.send(Request::ListSynthesisVoices).await
     }
 
-    /// Set the rate of speech. n is an integer value within the range from -100 to 100, lower values meaning slower speech.
-    pub async fn set_rate(&mut self, scope: ClientScope, value: i8) -> ClientResult<&mut Self> {
+    /// Set the rate of speech. Lower values mean slower speech.
+    pub async fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<&mut Self> {
         self.send(Request::SetRate(scope, value)).await
     }
 
@@ -439,8 +564,8 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
         self.send(Request::GetRate).await
     }
 
-    /// Set the pitch of speech. n is an integer value within the range from -100 to 100.
-    pub async fn set_pitch(&mut self, scope: ClientScope, value: i8) -> ClientResult<&mut Self> {
+    /// Set the pitch of speech.
+    pub async fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<&mut Self> {
         self.send(Request::SetPitch(scope, value)).await
     }
 
@@ -449,8 +574,26 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
         self.send(Request::GetPitch).await
     }
 
-    /// Set the volume of speech. n is an integer value within the range from -100 to 100.
-    pub async fn set_volume(&mut self, scope: ClientScope, value: i8) -> ClientResult<&mut Self> {
+    /// Set the pitch range of speech.
+    pub async fn set_pitch_range(
+        &mut self,
+        scope: ClientScope,
+        value: PitchRange,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetPitchRange(scope, value)).await
+    }
+
+    /// Get the current pitch range value.
+    pub async fn get_pitch_range(&mut self) -> ClientResult<&mut Self> {
+        self.send(Request::GetPitchRange).await
+    }
+
+    /// Set the volume of speech.
+    pub async fn set_volume(
+        &mut self,
+        scope: ClientScope,
+        value: Volume,
+    ) -> ClientResult<&mut Self> {
         self.send(Request::SetVolume(scope, value)).await
     }
 
@@ -496,6 +639,22 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
         self.send(Request::SetHistory(scope, value)).await
     }
 
+    /// Return the client id, querying the server if it wasn't sent with the `CLIENT_NAME` reply.
+    pub async fn get_client_id(&mut self) -> ClientResult<ClientId> {
+        match self.client_id {
+            Some(id) => Ok(id),
+            None => {
+                let id = self
+                    .history_get_client_id()
+                    .await?
+                    .receive_client_id()
+                    .await?;
+                self.client_id = Some(id);
+                Ok(id)
+            }
+        }
+    }
+
     /// Get clients in history.
     pub async fn history_get_clients(&mut self) -> ClientResult<&mut Self> {
         self.send(Request::HistoryGetClients).await
@@ -595,6 +754,11 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
             .await
     }
 
+    /// List the commands understood by the server
+    pub async fn help(&mut self) -> ClientResult<&mut Self> {
+        self.send(Request::Help).await
+    }
+
     /// Close the connection
     pub async fn quit(&mut self) -> ClientResult<&mut Self> {
         self.send(Request::Quit).await
@@ -664,11 +828,12 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
     }
 
     /// Receive client id
+    ///
+    /// Accept both the answer to `HISTORY GET CLIENT_ID` and a `CLIENT_NAME` reply carrying the id.
     pub async fn receive_client_id(&mut self) -> ClientResult<ClientId> {
-        self.receive_string(OK_CLIENT_ID_SENT).await.and_then(|s| {
-            s.parse()
-                .map_err(|_| ClientError::invalid_data("invalid client id"))
-        })
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines).await?;
+        parse_client_id(&status, &lines)
     }
 
     /// Receive a list of synthesis voices
@@ -681,30 +846,9 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
     /// Receive a notification
     pub async fn receive_event(&mut self) -> ClientResult<Event> {
         let mut lines = Vec::new();
-        self.receive_answer(&mut lines).await.and_then(|status| {
-            if lines.len() < 2 {
-                Err(ClientError::unexpected_eof("event truncated"))
-            } else {
-                let message = &lines[0];
-                let client = &lines[1];
-                match status.code {
-                    700 => {
-                        if lines.len() != 3 {
-                            Err(ClientError::unexpected_eof("index markevent truncated"))
-                        } else {
-                            let mark = lines[3].to_owned();
-                            Ok(Event::index_mark(mark, message, client))
-                        }
-                    }
-                    701 => Ok(Event::begin(message, client)),
-                    702 => Ok(Event::end(message, client)),
-                    703 => Ok(Event::cancel(message, client)),
-                    704 => Ok(Event::pause(message, client)),
-                    705 => Ok(Event::resume(message, client)),
-                    _ => Err(ClientError::invalid_data("wrong status code for event")),
-                }
-            }
-        })
+        self.receive_answer(&mut lines)
+            .await
+            .and_then(|status| parse_event(&status, &lines))
     }
 
     /// Receive a list of client status from history.
@@ -714,9 +858,18 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
             .and_then(|lines| parse_typed_lines::<HistoryClientStatus>(&lines))
     }
 
-    /// Check the result of `set_client_name`.
+    /// Check the result of `set_client_name` and store the client id if the server sent it.
     pub async fn check_client_name_set(&mut self) -> ClientResult<&mut Self> {
-        self.check_status(OK_CLIENT_NAME_SET).await
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines).await?;
+        if status.code == OK_CLIENT_NAME_SET {
+            let handshake = parse_handshake(&status, &lines);
+            self.client_id = handshake.client_id;
+            self.handshake = Some(handshake);
+            Ok(self)
+        } else {
+            Err(ClientError::UnexpectedStatus(status.code))
+        }
     }
 
     /// Check if server accept data.
@@ -724,3 +877,5 @@ impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> AsyncClient<R, W> {
         self.check_status(OK_RECEIVING_DATA).await
     }
 }
+
+crate::async_client::impl_async_ssip_client!();
@@ -6,13 +6,38 @@
 // license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
-
-use std::io::{self, Read, Write};
+use log::debug;
+use std::borrow::Cow;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+#[cfg(not(feature = "async-mio"))]
+use std::time::Instant;
 
 use crate::constants::*;
+use crate::keepalive::{probe_result, Keepalive};
+#[cfg(not(feature = "async-mio"))]
+use crate::net::ReadTimeout;
+use crate::parse::{
+    parse_client_id, parse_debug_path, parse_event, parse_event_id, parse_handshake,
+    parse_single_integer, parse_single_value, parse_speech_parameter, parse_status_line,
+    parse_typed_lines, parse_typed_lines_lenient, InvalidLines,
+};
 use crate::protocol::{
-    flush_lines, parse_event_id, parse_single_integer, parse_single_value, parse_typed_lines,
-    write_lines,
+    receive_answer, receive_raw_answer, text_lines, write_data_block, write_lines,
+    write_text_bytes, PartialAnswer,
+};
+use crate::retry::RetryPolicy;
+use crate::speech::{
+    boundary_offsets, escape_xml, split_chunks, Boundary, EscapePolicy, SpeechBuilder,
+    TextNormalizer, DEFAULT_MAX_MESSAGE_BYTES,
 };
 use crate::types::*;
 
@@ -33,11 +58,11 @@ fn on_off(value: bool) -> &'static str {
 }
 
 macro_rules! send_one_line {
-    ($self:expr, $fmt:expr, $( $arg:expr ),+) => {
-        flush_lines(&mut $self.output, &[format!($fmt, $( $arg ),+).as_str()])
+    ($output:expr, $fmt:expr, $( $arg:expr ),+) => {
+        write_lines($output, &[format!($fmt, $( $arg ),+).as_str()])
     };
-    ($self:expr, $fmt:expr) => {
-        flush_lines(&mut $self.output, &[$fmt])
+    ($output:expr, $fmt:expr) => {
+        write_lines($output, &[$fmt])
     }
 }
 
@@ -50,15 +75,406 @@ macro_rules! send_toggle {
     };
 }
 
-macro_rules! send_range {
-    ($output:expr, $fmt:expr, $scope:expr, $val:expr) => {
-        send_one_line!(
-            $output,
-            $fmt,
-            $scope,
-            std::cmp::max(-100, std::cmp::min(100, $val))
-        )
+/// Send lines of text (terminated by a single dot).
+fn write_text_lines<W: Write>(output: &mut W, lines: &[String]) -> ClientResult<()> {
+    write_data_block(output, lines.iter().map(String::as_str))
+}
+
+/// Send one line of text (terminated by a single dot).
+fn write_text_line<W: Write>(output: &mut W, line: &str) -> ClientResult<()> {
+    write_data_block(output, [line])
+}
+
+/// Write a request on the output without flushing it.
+///
+/// Return the message or client whose id was written in the scope of the request.
+pub(crate) fn write_request<W: Write>(
+    output: &mut W,
+    request: Request,
+) -> ClientResult<Option<Target>> {
+    let target = Target::of(&request);
+    match request {
+        Request::SetName(client_name) => {
+            client_name.validate()?;
+            send_one_line!(
+                output,
+                "SET self CLIENT_NAME {}:{}:{}",
+                client_name.user,
+                client_name.application,
+                client_name.component
+            )
+        }
+        Request::Speak => send_one_line!(output, "SPEAK"),
+        Request::SendLine(line) => write_text_line(output, &line),
+        Request::SendLines(lines) => write_text_lines(output, &lines),
+        Request::SpeakChar(ch) => send_one_line!(output, "CHAR {}", ch),
+        Request::SpeakKey(key) => send_one_line!(output, "KEY {}", key),
+        Request::Stop(scope) => send_one_line!(output, "STOP {}", scope),
+        Request::Cancel(scope) => send_one_line!(output, "CANCEL {}", scope),
+        Request::Pause(scope) => send_one_line!(output, "PAUSE {}", scope),
+        Request::Resume(scope) => send_one_line!(output, "RESUME {}", scope),
+        Request::StopClient(scope) => send_one_line!(output, "STOP {}", scope),
+        Request::CancelClient(scope) => send_one_line!(output, "CANCEL {}", scope),
+        Request::PauseClient(scope) => send_one_line!(output, "PAUSE {}", scope),
+        Request::ResumeClient(scope) => send_one_line!(output, "RESUME {}", scope),
+        Request::SetPriority(prio) => send_one_line!(output, "SET self PRIORITY {}", prio),
+        Request::SetDebug(value) => send_toggle!(output, "SET all DEBUG {}", value),
+        Request::SetOutputModule(scope, value) => {
+            send_one_line!(output, "SET {} OUTPUT_MODULE {}", scope, value)
+        }
+        Request::GetOutputModule => send_one_line!(output, "GET OUTPUT_MODULE"),
+        Request::ListOutputModules => send_one_line!(output, "LIST OUTPUT_MODULES"),
+        Request::SetLanguage(scope, lang) => {
+            send_one_line!(output, "SET {} LANGUAGE {}", scope, lang)
+        }
+        Request::GetLanguage => send_one_line!(output, "GET LANGUAGE"),
+        Request::SetSsmlMode(value) => send_toggle!(output, "SET self SSML_MODE {}", value),
+        Request::SetPunctuationMode(scope, mode) => {
+            send_one_line!(output, "SET {} PUNCTUATION {}", scope, mode)
+        }
+        Request::GetPunctuationMode => send_one_line!(output, "GET PUNCTUATION"),
+        Request::SetSpelling(scope, value) => {
+            send_toggle!(output, "SET {} SPELLING {}", scope, value)
+        }
+        Request::SetCapitalLettersRecognitionMode(scope, mode) => {
+            send_one_line!(output, "SET {} CAP_LET_RECOGN {}", scope, mode)
+        }
+        Request::SetVoiceType(scope, value) => {
+            send_one_line!(output, "SET {} VOICE_TYPE {}", scope, value)
+        }
+        Request::GetVoiceType => send_one_line!(output, "GET VOICE_TYPE"),
+        Request::ListVoiceTypes => send_one_line!(output, "LIST VOICES"),
+        Request::SetSynthesisVoice(scope, value) => {
+            send_one_line!(output, "SET {} SYNTHESIS_VOICE {}", scope, value)
+        }
+        Request::ListSynthesisVoices => send_one_line!(output, "LIST SYNTHESIS_VOICES"),
+        Request::SetRate(scope, value) => send_one_line!(output, "SET {} RATE {}", scope, value),
+        Request::GetRate => send_one_line!(output, "GET RATE"),
+        Request::SetPitch(scope, value) => {
+            send_one_line!(output, "SET {} PITCH {}", scope, value)
+        }
+        Request::GetPitch => send_one_line!(output, "GET PITCH"),
+        Request::SetPitchRange(scope, value) => {
+            send_one_line!(output, "SET {} PITCH_RANGE {}", scope, value)
+        }
+        Request::GetPitchRange => send_one_line!(output, "GET PITCH_RANGE"),
+        Request::SetVolume(scope, value) => {
+            send_one_line!(output, "SET {} VOLUME {}", scope, value)
+        }
+        Request::GetVolume => send_one_line!(output, "GET VOLUME"),
+        Request::SetPauseContext(scope, value) => {
+            send_one_line!(output, "SET {} PAUSE_CONTEXT {}", scope, value)
+        }
+        Request::SetHistory(scope, value) => {
+            send_toggle!(output, "SET {} HISTORY {}", scope, value)
+        }
+        Request::SetNotification(ntype, value) => {
+            send_toggle!(output, "SET self NOTIFICATION {} {}", ntype, value)
+        }
+        Request::Begin => send_one_line!(output, "BLOCK BEGIN"),
+        Request::End => send_one_line!(output, "BLOCK END"),
+        Request::HistoryGetClients => send_one_line!(output, "HISTORY GET CLIENT_LIST"),
+        Request::HistoryGetClientId => send_one_line!(output, "HISTORY GET CLIENT_ID"),
+        Request::HistoryGetClientMsgs(scope, start, number) => send_one_line!(
+            output,
+            "HISTORY GET CLIENT_MESSAGES {} {}_{}",
+            scope,
+            start,
+            number
+        ),
+        Request::HistoryGetLastMsgId => send_one_line!(output, "HISTORY GET LAST"),
+        Request::HistoryGetMsg(id) => send_one_line!(output, "HISTORY GET MESSAGE {}", id),
+        Request::HistoryCursorGet => send_one_line!(output, "HISTORY CURSOR GET"),
+        Request::HistoryCursorSet(scope, pos) => {
+            send_one_line!(output, "HISTORY CURSOR SET {} {}", scope, pos)
+        }
+        Request::HistoryCursorMove(direction) => {
+            send_one_line!(output, "HISTORY CURSOR {}", direction)
+        }
+        Request::HistorySpeak(id) => send_one_line!(output, "HISTORY SAY {}", id),
+        Request::HistorySort(direction, key) => {
+            send_one_line!(output, "HISTORY SORT {} {}", direction, key)
+        }
+        Request::HistorySetShortMsgLength(length) => {
+            send_one_line!(output, "HISTORY SET SHORT_MESSAGE_LENGTH {}", length)
+        }
+        Request::HistorySetMsgTypeOrdering(ordering) => {
+            send_one_line!(
+                output,
+                "HISTORY SET MESSAGE_TYPE_ORDERING \"{}\"",
+                ordering
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            )
+        }
+        Request::HistorySearch(scope, condition) => {
+            send_one_line!(output, "HISTORY SEARCH {} \"{}\"", scope, condition)
+        }
+        Request::Help => send_one_line!(output, "HELP"),
+        Request::Quit => send_one_line!(output, "QUIT"),
+        _ => Err(ClientError::io_error(
+            io::ErrorKind::Unsupported,
+            "request not supported by this client",
+        )),
+    }?;
+    Ok(target)
+}
+
+/// Read one response from the input.
+pub(crate) fn read_response<R: BufRead>(
+    input: &mut R,
+    partial: &mut PartialAnswer,
+) -> ClientResult<Response> {
+    const MSG_CURSOR_SET_FIRST: &str = "OK CURSOR SET FIRST";
+    let mut lines = Vec::new();
+    let status = receive_answer(input, partial, Some(&mut lines))?;
+    match status.code {
+        OK_LANGUAGE_SET => Ok(Response::LanguageSet),
+        OK_PRIORITY_SET => Ok(Response::PrioritySet),
+        OK_RATE_SET => Ok(Response::RateSet),
+        OK_PITCH_SET => Ok(Response::PitchSet),
+        OK_PUNCTUATION_SET => Ok(Response::PunctuationSet),
+        OK_CAP_LET_RECOGN_SET => Ok(Response::CapLetRecognSet),
+        OK_SPELLING_SET => Ok(Response::SpellingSet),
+        OK_CLIENT_NAME_SET => Ok(Response::ClientNameSet),
+        OK_VOICE_SET => Ok(Response::VoiceSet),
+        OK_STOPPED => Ok(Response::Stopped),
+        OK_PAUSED => Ok(Response::Paused),
+        OK_RESUMED => Ok(Response::Resumed),
+        OK_CANCELED => Ok(Response::Canceled),
+        OK_TABLE_SET => Ok(Response::TableSet),
+        OK_OUTPUT_MODULE_SET => Ok(Response::OutputModuleSet),
+        OK_PAUSE_CONTEXT_SET => Ok(Response::PauseContextSet),
+        OK_VOLUME_SET => Ok(Response::VolumeSet),
+        OK_SSML_MODE_SET => Ok(Response::SsmlModeSet),
+        // Warning OK_CUR_SET_FIRST == OK_NOTIFICATION_SET == 220. Matching message to make the difference
+        OK_NOTIFICATION_SET => {
+            if status.message == MSG_CURSOR_SET_FIRST {
+                //OK_CUR_SET_FIRST => Ok(Response::HistoryCurSetFirst)
+                Ok(Response::HistoryCurSetFirst)
+            } else {
+                Ok(Response::NotificationSet)
+            }
+        }
+        OK_CUR_SET_LAST => Ok(Response::HistoryCurSetLast),
+        OK_CUR_SET_POS => Ok(Response::HistoryCurSetPos),
+        OK_PITCH_RANGE_SET => Ok(Response::PitchRangeSet),
+        OK_DEBUG_SET => Ok(Response::DebugSet),
+        OK_CUR_MOV_FOR => Ok(Response::HistoryCurMoveFor),
+        OK_CUR_MOV_BACK => Ok(Response::HistoryCurMoveBack),
+        OK_MESSAGE_QUEUED => Ok(Response::MessageQueued),
+        OK_SND_ICON_QUEUED => Ok(Response::SoundIconQueued),
+        OK_MSG_CANCELED => Ok(Response::MessageCanceled),
+        OK_RECEIVING_DATA => Ok(Response::ReceivingData),
+        OK_BYE => Ok(Response::Bye),
+        OK_CLIENTS_LIST_SENT => Ok(Response::HistoryClientListSent(parse_typed_lines::<
+            HistoryClientStatus,
+        >(&lines)?)),
+        OK_MSGS_LIST_SENT => Ok(Response::HistoryMsgsListSent(lines)),
+        OK_LAST_MSG => Ok(Response::HistoryLastMsg(parse_single_value(&lines)?)),
+        OK_CUR_POS_RET => Ok(Response::HistoryCurPosRet(parse_single_value(&lines)?)),
+        OK_TABLE_LIST_SENT => Ok(Response::TableListSent(lines)),
+        OK_CLIENT_ID_SENT => Ok(Response::HistoryClientIdSent(parse_single_integer(&lines)?)),
+        OK_MSG_TEXT_SENT => Ok(Response::MessageTextSent),
+        OK_HELP_SENT => Ok(Response::HelpSent(lines)),
+        OK_VOICES_LIST_SENT => Ok(Response::VoicesListSent(
+            parse_typed_lines::<SynthesisVoice>(&lines)?,
+        )),
+        OK_OUTPUT_MODULES_LIST_SENT => Ok(Response::OutputModulesListSent(lines)),
+        OK_GET => Ok(Response::Get(parse_single_value(&lines)?)),
+        OK_INSIDE_BLOCK => Ok(Response::InsideBlock),
+        OK_OUTSIDE_BLOCK => Ok(Response::OutsideBlock),
+        OK_NOT_IMPLEMENTED => Ok(Response::NotImplemented),
+        EVENT_INDEX_MARK => match lines.len() {
+            n @ 0..=2 => Err(ClientError::TooFewLines {
+                expected: 3,
+                got: n,
+            }),
+            3 => Ok(Response::EventIndexMark(
+                parse_event_id(&lines)?,
+                lines[2].to_owned(),
+            )),
+            n => Err(ClientError::TooManyLines {
+                expected: 3,
+                got: n,
+            }),
+        },
+        EVENT_BEGIN => Ok(Response::EventBegin(parse_event_id(&lines)?)),
+        EVENT_END => Ok(Response::EventEnd(parse_event_id(&lines)?)),
+        EVENT_CANCELED => Ok(Response::EventCanceled(parse_event_id(&lines)?)),
+        EVENT_PAUSED => Ok(Response::EventPaused(parse_event_id(&lines)?)),
+        EVENT_RESUMED => Ok(Response::EventResumed(parse_event_id(&lines)?)),
+        _ => panic!("error should have been caught earlier"),
+    }
+}
+
+/// Read a notification from the input.
+fn read_event<R: BufRead>(input: &mut R, partial: &mut PartialAnswer) -> ClientResult<Event> {
+    let mut lines = Vec::new();
+    receive_answer(input, partial, Some(&mut lines)).and_then(|status| parse_event(&status, &lines))
+}
+
+/// Convert an event response into an event, other responses are returned unchanged.
+fn response_event(response: Response) -> Result<Event, Response> {
+    let (ntype, id) = match response {
+        Response::EventIndexMark(id, mark) => (EventType::IndexMark(mark), id),
+        Response::EventBegin(id) => (EventType::Begin, id),
+        Response::EventEnd(id) => (EventType::End, id),
+        Response::EventCanceled(id) => (EventType::Cancel, id),
+        Response::EventPaused(id) => (EventType::Pause, id),
+        Response::EventResumed(id) => (EventType::Resume, id),
+        response => return Err(response),
+    };
+    Ok(Event { ntype, id })
+}
+
+/// Convert an event into the equivalent response.
+fn event_response(event: Event) -> Response {
+    match event.ntype {
+        EventType::IndexMark(mark) => Response::EventIndexMark(event.id, mark),
+        EventType::Begin => Response::EventBegin(event.id),
+        EventType::End => Response::EventEnd(event.id),
+        EventType::Cancel => Response::EventCanceled(event.id),
+        EventType::Pause => Response::EventPaused(event.id),
+        EventType::Resume => Response::EventResumed(event.id),
+    }
+}
+
+/// Parse the lines of an answer read by [`Client::execute_raw`] if it's an event.
+fn raw_event(answer: &[String]) -> ClientResult<Option<Event>> {
+    let status = match answer.last().map(|line| parse_status_line(line)) {
+        Some(Ok(status)) if (EVENT_INDEX_MARK..=EVENT_RESUMED).contains(&status.code) => status,
+        _ => return Ok(None),
+    };
+    let lines = answer[..answer.len() - 1]
+        .iter()
+        .map(|line| line.get(4..).unwrap_or_default().to_string())
+        .collect::<Vec<String>>();
+    parse_event(&status, &lines).map(Some)
+}
+
+/// Find the first `major.minor` number following the word `version` in help lines.
+fn parse_protocol_version(lines: &[String]) -> Option<(u16, u16)> {
+    lines.iter().find_map(|line| {
+        let mut words = line.split_whitespace();
+        words.find(|word| word.eq_ignore_ascii_case("version"))?;
+        let mut numbers = words.next()?.split('.');
+        let major = numbers.next()?.parse().ok()?;
+        let minor = numbers.next()?.parse().ok()?;
+        Some((major, minor))
+    })
+}
+
+/// Message or client whose id is in the scope of a request.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Target {
+    Message(MessageId),
+    Client(ClientId),
+}
+
+impl Target {
+    fn of(request: &Request) -> Option<Self> {
+        match (request.message_scope(), request.scope()) {
+            (Some(MessageScope::Message(id)), _) => Some(Target::Message(*id)),
+            (_, Some(ClientScope::Client(id))) => Some(Target::Client(*id)),
+            _ => None,
+        }
+    }
+}
+
+/// Report [`ERR_ID_NOT_EXIST`] and [`ERR_NO_SUCH_CLIENT`] with the targeted id.
+fn unknown_id(status: StatusLine, target: Option<Target>) -> ClientError {
+    match (status.code, target) {
+        (ERR_ID_NOT_EXIST, Some(Target::Message(id)))
+        | (ERR_NO_SUCH_CLIENT, Some(Target::Client(id))) => ClientError::UnknownId { id, status },
+        _ => ClientError::Ssip(status),
+    }
+}
+
+/// Rank how well a voice matches a language tag such as `de` or `de-AT`, lower is better.
+///
+/// The exact tag or a dialect equal to the region is preferred over the same primary language.
+fn voice_language_rank(voice: &SynthesisVoice, lang: &str) -> Option<u8> {
+    let language = voice.language.as_deref()?;
+    let primary = |tag: &str| tag.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    let region = lang.split_once(['-', '_']).map(|(_, region)| region);
+    let same_dialect = match (region, voice.dialect.as_deref()) {
+        (Some(region), Some(dialect)) => region.eq_ignore_ascii_case(dialect),
+        _ => false,
     };
+    if language.eq_ignore_ascii_case(lang) || (same_dialect && primary(language) == primary(lang)) {
+        Some(0)
+    } else if primary(language) == primary(lang) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Maximum number of finished messages whose state is remembered.
+const FINISHED_MESSAGES_CAPACITY: usize = 256;
+
+/// State of the messages queued by the client, updated from the notifications.
+#[derive(Default)]
+struct MessageTracker {
+    states: HashMap<MessageId, MessageState>,
+    finished: VecDeque<MessageId>,
+    last: Option<MessageId>,
+}
+
+impl MessageTracker {
+    fn queued(&mut self, id: MessageId) {
+        self.states.insert(id, MessageState::Queued);
+        self.last = Some(id);
+    }
+
+    fn finished(&mut self, id: MessageId) {
+        if self.states.insert(id, MessageState::Finished) != Some(MessageState::Finished) {
+            self.finished.push_back(id);
+            if self.finished.len() > FINISHED_MESSAGES_CAPACITY {
+                if let Some(oldest) = self.finished.pop_front() {
+                    self.states.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, message: &str, ntype: &EventType) {
+        let Ok(id) = message.parse::<MessageId>() else {
+            return;
+        };
+        match ntype {
+            EventType::Begin => {
+                self.states.insert(id, MessageState::Speaking);
+            }
+            EventType::End | EventType::Cancel => self.finished(id),
+            _ => (),
+        }
+    }
+
+    fn update_from_response(&mut self, response: &Response) {
+        match response {
+            Response::EventBegin(id) => self.update(&id.message, &EventType::Begin),
+            Response::EventEnd(id) => self.update(&id.message, &EventType::End),
+            Response::EventCanceled(id) => self.update(&id.message, &EventType::Cancel),
+            _ => (),
+        }
+    }
+
+    fn is_speaking(&self) -> bool {
+        self.states
+            .values()
+            .any(|state| *state == MessageState::Speaking)
+    }
+
+    fn state(&self, id: MessageId) -> MessageState {
+        self.states
+            .get(&id)
+            .copied()
+            .unwrap_or(MessageState::Unknown)
+    }
 }
 
 /// SSIP client on generic stream
@@ -66,16 +482,102 @@ macro_rules! send_range {
 /// There are two ways to send requests and receive responses:
 /// * Either with the generic [`Client::send`] and [`Client::receive`]
 /// * Or with the specific methods such as [`Client::set_rate`], ..., [`Client::get_rate`], ...
+///
+/// When dropped, the client sends `QUIT` to the server unless [`Client::quit`] or
+/// [`Client::forget_quit`] was called. Errors can't be reported from `drop`, so call
+/// [`Client::quit`] explicitly when the shutdown must be checked.
+///
+/// Methods taking a scope accept `()` for the default one, the current client or the last
+/// message: `client.set_rate((), rate)` is the same as `client.set_rate(ClientScope::Current, rate)`.
+///
+/// The client is `Send` and `Sync` when the stream is, as it is for `UnixStream` and
+/// `TcpStream`. It can be moved into another thread but requests need `&mut self`, so
+/// sharing it between threads requires a `Mutex`.
 pub struct Client<S: Read + Write + Source> {
     input: io::BufReader<S>,
-    output: io::BufWriter<S>,
+    partial: PartialAnswer,
+    writer: ClientWriter<S>,
+    client_id: Option<ClientId>,
+    handshake: Option<Handshake>,
+    messages: MessageTracker,
+    events: VecDeque<Event>,
+    notifications: HashSet<NotificationType>,
+    in_block: bool,
+    spelling: bool,
+    ssml_mode: bool,
+    priority: Priority,
+    retry_policy: RetryPolicy,
+    protocol_version: Option<(u16, u16)>,
+    keepalive: Keepalive,
+    escape_policy: Option<EscapePolicy>,
+    max_message_bytes: Option<usize>,
+    chunking: bool,
+    normalizer: TextNormalizer,
+    target: Option<Target>,
+    pending_answers: usize,
+    pause_context: u32,
+    saved_pause_context: Option<u32>,
 }
 
 impl<S: Read + Write + Source> Client<S> {
     /// Create a SSIP client on the reader and writer.
     pub(crate) fn new(input: io::BufReader<S>, output: io::BufWriter<S>) -> Self {
         // https://stackoverflow.com/questions/58467659/how-to-store-tcpstream-with-bufreader-and-bufwriter-in-a-data-structure
-        Self { input, output }
+        Self {
+            input,
+            partial: PartialAnswer::default(),
+            writer: ClientWriter {
+                output,
+                quit_on_drop: true,
+            },
+            client_id: None,
+            handshake: None,
+            messages: MessageTracker::default(),
+            events: VecDeque::new(),
+            notifications: HashSet::new(),
+            in_block: false,
+            spelling: false,
+            ssml_mode: false,
+            priority: Priority::default(),
+            retry_policy: RetryPolicy::none(),
+            protocol_version: None,
+            keepalive: Keepalive::default(),
+            escape_policy: None,
+            max_message_bytes: Some(DEFAULT_MAX_MESSAGE_BYTES),
+            chunking: false,
+            normalizer: TextNormalizer::default(),
+            target: None,
+            pending_answers: 0,
+            pause_context: 0,
+            saved_pause_context: None,
+        }
+    }
+
+    /// Client id assigned by the server, if it was sent in the answer to [`Client::set_client_name`].
+    ///
+    /// It can be used to target this connection with [`ClientScope::Client`].
+    pub fn client_id(&self) -> Option<&ClientId> {
+        self.client_id.as_ref()
+    }
+
+    /// Answer to the last successful `SET self CLIENT_NAME`, if it was checked.
+    pub fn handshake(&self) -> Option<&Handshake> {
+        self.handshake.as_ref()
+    }
+
+    /// Get a reference to the underlying stream.
+    ///
+    /// Useful to query or tune socket options. Reading from or writing to the stream directly
+    /// bypasses the buffers of the client and corrupts the protocol.
+    pub fn get_ref(&self) -> &S {
+        self.input.get_ref()
+    }
+
+    /// Get a mutable reference to the underlying stream.
+    ///
+    /// Same restrictions as [`Client::get_ref`] apply.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.input.get_mut()
     }
 
     #[cfg(all(not(feature = "async-mio"), unix))]
@@ -87,143 +589,229 @@ impl<S: Read + Write + Source> Client<S> {
     #[cfg(all(not(feature = "async-mio"), unix))]
     /// Output source for asynchronous API based on `poll`.
     pub(crate) fn output_source(&self) -> &S {
-        self.output.get_ref()
+        self.writer.output.get_ref()
     }
 
     /// Send lines of text (terminated by a single dot).
+    ///
+    /// Lines are escaped as in [`encode_data_block`](crate::encode_data_block).
     pub fn send_lines(&mut self, lines: &[String]) -> ClientResult<&mut Self> {
-        const END_OF_DATA: [&str; 1] = ["."];
-        write_lines(
-            &mut self.output,
-            lines
-                .iter()
-                .map(|s| s.as_str())
-                .collect::<Vec<&str>>()
-                .as_slice(),
-        )?;
-        flush_lines(&mut self.output, &END_OF_DATA)?;
+        write_text_lines(&mut self.writer.output, lines)?;
         Ok(self)
     }
 
-    /// Send one line of text (terminated by a single dot).
+    /// Send one line of text (terminated by a single dot), escaped as in [`Client::send_lines`].
     pub fn send_line(&mut self, line: &str) -> ClientResult<&mut Self> {
-        const END_OF_DATA: &str = ".";
-        flush_lines(&mut self.output, &[line, END_OF_DATA])?;
+        write_text_line(&mut self.writer.output, line)?;
         Ok(self)
     }
 
     /// Send a request
+    ///
+    /// Inside a block, requests are buffered until the end of the block or until a response
+    /// is read. Use [`Client::flush`] to send them earlier.
     pub fn send(&mut self, request: Request) -> ClientResult<&mut Self> {
-        match request {
-            Request::SetName(client_name) => send_one_line!(
-                self,
-                "SET self CLIENT_NAME {}:{}:{}",
-                client_name.user,
-                client_name.application,
-                client_name.component
-            ),
-            Request::Speak => send_one_line!(self, "SPEAK"),
-            Request::SendLine(line) => self.send_line(&line).map(|_| ()),
-            Request::SendLines(lines) => self.send_lines(&lines).map(|_| ()),
-            Request::SpeakChar(ch) => send_one_line!(self, "CHAR {}", ch),
-            Request::SpeakKey(key) => send_one_line!(self, "KEY {}", key),
-            Request::Stop(scope) => send_one_line!(self, "STOP {}", scope),
-            Request::Cancel(scope) => send_one_line!(self, "CANCEL {}", scope),
-            Request::Pause(scope) => send_one_line!(self, "PAUSE {}", scope),
-            Request::Resume(scope) => send_one_line!(self, "RESUME {}", scope),
-            Request::SetPriority(prio) => send_one_line!(self, "SET self PRIORITY {}", prio),
-            Request::SetDebug(value) => send_toggle!(self, "SET all DEBUG {}", value),
-            Request::SetOutputModule(scope, value) => {
-                send_one_line!(self, "SET {} OUTPUT_MODULE {}", scope, value)
-            }
-            Request::GetOutputModule => send_one_line!(self, "GET OUTPUT_MODULE"),
-            Request::ListOutputModules => send_one_line!(self, "LIST OUTPUT_MODULES"),
-            Request::SetLanguage(scope, lang) => {
-                send_one_line!(self, "SET {} LANGUAGE {}", scope, lang)
-            }
-            Request::GetLanguage => send_one_line!(self, "GET LANGUAGE"),
-            Request::SetSsmlMode(value) => send_toggle!(self, "SET self SSML_MODE {}", value),
-            Request::SetPunctuationMode(scope, mode) => {
-                send_one_line!(self, "SET {} PUNCTUATION {}", scope, mode)
-            }
-            Request::SetSpelling(scope, value) => {
-                send_toggle!(self, "SET {} SPELLING {}", scope, value)
-            }
-            Request::SetCapitalLettersRecognitionMode(scope, mode) => {
-                send_one_line!(self, "SET {} CAP_LET_RECOGN {}", scope, mode)
-            }
-            Request::SetVoiceType(scope, value) => {
-                send_one_line!(self, "SET {} VOICE_TYPE {}", scope, value)
-            }
-            Request::GetVoiceType => send_one_line!(self, "GET VOICE_TYPE"),
-            Request::ListVoiceTypes => send_one_line!(self, "LIST VOICES"),
-            Request::SetSynthesisVoice(scope, value) => {
-                send_one_line!(self, "SET {} SYNTHESIS_VOICE {}", scope, value)
-            }
-            Request::ListSynthesisVoices => send_one_line!(self, "LIST SYNTHESIS_VOICES"),
-            Request::SetRate(scope, value) => send_range!(self, "SET {} RATE {}", scope, value),
-            Request::GetRate => send_one_line!(self, "GET RATE"),
-            Request::SetPitch(scope, value) => send_range!(self, "SET {} PITCH {}", scope, value),
-            Request::GetPitch => send_one_line!(self, "GET PITCH"),
-            Request::SetVolume(scope, value) => {
-                send_range!(self, "SET {} VOLUME {}", scope, value)
-            }
-            Request::GetVolume => send_one_line!(self, "GET VOLUME"),
-            Request::SetPauseContext(scope, value) => {
-                send_one_line!(self, "SET {} PAUSE_CONTEXT {}", scope, value)
-            }
-            Request::SetHistory(scope, value) => {
-                send_toggle!(self, "SET {} HISTORY {}", scope, value)
-            }
-            Request::SetNotification(ntype, value) => {
-                send_toggle!(self, "SET self NOTIFICATION {} {}", ntype, value)
-            }
-            Request::Begin => send_one_line!(self, "BLOCK BEGIN"),
-            Request::End => send_one_line!(self, "BLOCK END"),
-            Request::HistoryGetClients => send_one_line!(self, "HISTORY GET CLIENT_LIST"),
-            Request::HistoryGetClientId => send_one_line!(self, "HISTORY GET CLIENT_ID"),
-            Request::HistoryGetClientMsgs(scope, start, number) => send_one_line!(
-                self,
-                "HISTORY GET CLIENT_MESSAGES {} {}_{}",
-                scope,
-                start,
-                number
-            ),
-            Request::HistoryGetLastMsgId => send_one_line!(self, "HISTORY GET LAST"),
-            Request::HistoryGetMsg(id) => send_one_line!(self, "HISTORY GET MESSAGE {}", id),
-            Request::HistoryCursorGet => send_one_line!(self, "HISTORY CURSOR GET"),
-            Request::HistoryCursorSet(scope, pos) => {
-                send_one_line!(self, "HISTORY CURSOR SET {} {}", scope, pos)
-            }
-            Request::HistoryCursorMove(direction) => {
-                send_one_line!(self, "HISTORY CURSOR {}", direction)
-            }
-            Request::HistorySpeak(id) => send_one_line!(self, "HISTORY SAY {}", id),
-            Request::HistorySort(direction, key) => {
-                send_one_line!(self, "HISTORY SORT {} {}", direction, key)
-            }
-            Request::HistorySetShortMsgLength(length) => {
-                send_one_line!(self, "HISTORY SET SHORT_MESSAGE_LENGTH {}", length)
-            }
-            Request::HistorySetMsgTypeOrdering(ordering) => {
-                send_one_line!(
-                    self,
-                    "HISTORY SET MESSAGE_TYPE_ORDERING \"{}\"",
-                    ordering
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<String>>()
-                        .join(" ")
-                )
-            }
-            Request::HistorySearch(scope, condition) => {
-                send_one_line!(self, "HISTORY SEARCH {} \"{}\"", scope, condition)
-            }
-            Request::Quit => send_one_line!(self, "QUIT"),
-        }?;
+        self.write(request)?;
+        if !self.in_block {
+            self.writer.output.flush()?;
+        }
         Ok(self)
     }
 
+    /// Send a request whose answer is discarded.
+    ///
+    /// The answer is not awaited: it's read and dropped before the next answer is received,
+    /// so errors returned by the server for this request are lost. It's meant for commands
+    /// answered by a single status line, such as `SET`. Commands followed by data, such
+    /// as [`Request::Speak`], must not be sent this way.
+    ///
+    /// Beware that the answers keep pending in the socket until another answer or event is
+    /// read. [`Client::pending_answers`] tells how many are left.
+    pub fn send_no_wait(&mut self, request: Request) -> ClientResult<&mut Self> {
+        self.send(request)?;
+        self.pending_answers += 1;
+        Ok(self)
+    }
+
+    /// Number of answers to requests sent with [`Client::send_no_wait`] not read yet.
+    pub fn pending_answers(&self) -> usize {
+        self.pending_answers
+    }
+
+    /// Read and drop the answers to requests sent with [`Client::send_no_wait`].
+    fn discard_pending_answers(&mut self) -> ClientResult<()> {
+        while self.pending_answers > 0 {
+            match self.read_status(&mut Vec::new()) {
+                Ok(_) => (),
+                Err(ClientError::Ssip(status)) => debug!("discarded answer: {}", status),
+                Err(err) => return Err(err),
+            }
+            self.pending_answers -= 1;
+        }
+        Ok(())
+    }
+
+    /// Write a request in the output buffer.
+    fn write(&mut self, request: Request) -> ClientResult<()> {
+        self.keepalive.touch();
+        self.target = None;
+        match &request {
+            Request::Quit => self.writer.quit_on_drop = false,
+            Request::Begin => self.in_block = true,
+            Request::End => self.in_block = false,
+            Request::SetSpelling(ClientScope::Current | ClientScope::All, value) => {
+                self.spelling = *value
+            }
+            Request::SetSsmlMode(mode) => self.ssml_mode = *mode,
+            Request::SetPriority(prio) => self.priority = *prio,
+            Request::SetPauseContext(ClientScope::Current | ClientScope::All, value) => {
+                self.pause_context = *value
+            }
+            Request::SetNotification(NotificationType::All, false) => self.notifications.clear(),
+            Request::SetNotification(ntype, false) => {
+                self.notifications.remove(ntype);
+                self.notifications.remove(&NotificationType::All);
+            }
+            Request::SetNotification(ntype, true) => {
+                self.notifications.insert(*ntype);
+            }
+            _ => (),
+        }
+        self.target = write_request(&mut self.writer.output, request)?;
+        Ok(())
+    }
+
+    /// Accept answers whose lines end with a bare LF instead of CRLF.
+    ///
+    /// By default, the client is strict and such lines are rejected as invalid data.
+    /// Requests are always written with CRLF.
+    pub fn set_lenient_line_endings(&mut self, lenient: bool) -> &mut Self {
+        self.partial.set_lenient(lenient);
+        self
+    }
+
+    /// Set the policy used by [`Client::retry`]. By default, requests are not retried.
+    pub fn with_retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Run an exchange with the server and run it again while it fails with a retryable error.
+    ///
+    /// The exchange must send the request and read its answer, for example
+    /// `client.retry(|client| client.say_line(text))`.
+    /// Errors that are not retryable are returned immediately, see
+    /// [`ClientError::is_retryable`]. In particular, the exchange is not run again after a
+    /// timeout since its answer may still be pending.
+    pub fn retry<T, F>(&mut self, mut exchange: F) -> ClientResult<T>
+    where
+        F: FnMut(&mut Self) -> ClientResult<T>,
+    {
+        let mut retry = 0;
+        loop {
+            match exchange(self) {
+                Err(err) if err.is_retryable() && retry < self.retry_policy.max_retries => {
+                    std::thread::sleep(self.retry_policy.delay(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Start a pipeline of requests sent at once.
+    ///
+    /// See [`Pipeline`].
+    pub fn pipeline(&mut self) -> Pipeline<'_, S> {
+        Pipeline {
+            client: self,
+            requests: Vec::new(),
+        }
+    }
+
+    /// Send the buffered requests to the server.
+    pub fn flush(&mut self) -> ClientResult<&mut Self> {
+        self.writer.output.flush()?;
+        Ok(self)
+    }
+
+    /// Send a command line as is and return the lines of the answer, data and status.
+    ///
+    /// This is an escape hatch for commands that this crate doesn't support yet. No parsing
+    /// or validation is applied: the command is only terminated by CRLF and the answer
+    /// lines are returned without terminator, including error status lines. Commands with
+    /// embedded line breaks are refused. Events received meanwhile are queued as for the
+    /// other commands.
+    pub fn execute_raw(&mut self, command: &str) -> ClientResult<Vec<String>> {
+        self.write_command(command)?;
+        self.discard_pending_answers()?;
+        loop {
+            let answer = receive_raw_answer(&mut self.input, &mut self.partial)?;
+            match raw_event(&answer)? {
+                Some(event) => self.queue_event(event),
+                None => return Ok(answer),
+            }
+        }
+    }
+
+    /// Send a command line as is and return the data lines and the parsed status line.
+    ///
+    /// Same as [`Client::execute_raw`] except that a failure code is returned as
+    /// [`ClientError::Ssip`] and events received meanwhile are queued as for the
+    /// other commands.
+    pub fn execute(&mut self, command: &str) -> ClientResult<(Vec<String>, StatusLine)> {
+        self.write_command(command)?;
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines)?;
+        Ok((lines, status))
+    }
+
+    /// Write a command line that isn't a [`Request`] and flush it.
+    fn write_command(&mut self, command: &str) -> ClientResult<()> {
+        if command.contains(['\r', '\n']) {
+            return Err(ClientError::io_error(
+                io::ErrorKind::InvalidInput,
+                "line break in command",
+            ));
+        }
+        self.keepalive.touch();
+        self.target = None;
+        write_lines(&mut self.writer.output, &[command])?;
+        self.writer.output.flush()?;
+        Ok(())
+    }
+
+    /// Enable or disable the keepalive of an idle connection.
+    ///
+    /// Firewalls may silently drop idle connections, especially over TCP. When enabled,
+    /// [`Client::keepalive`] sends `GET RATE` if no request was sent during `interval`. Each
+    /// probe costs a round trip, so the interval should be long compared to the time between
+    /// requests of an active client.
+    ///
+    /// The synchronous client has no timer and spawns no thread: nothing is sent unless
+    /// [`Client::keepalive`] is called. The asynchronous clients schedule the probe on the
+    /// timer of the runtime with `wait_keepalive`.
+    pub fn set_keepalive(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.keepalive.set_interval(interval);
+        self
+    }
+
+    /// Probe the connection if the keepalive interval has elapsed without requests.
+    ///
+    /// Call it periodically, for example from the event loop. Return true if a probe was
+    /// sent. A lost connection is reported as [`ClientError::Disconnected`], while errors
+    /// returned by the server are ignored since they prove it's alive.
+    pub fn keepalive(&mut self) -> ClientResult<bool> {
+        if !self.keepalive.is_due() {
+            return Ok(false);
+        }
+        let mut lines = Vec::new();
+        let result = self
+            .send(Request::GetRate)
+            .and_then(|client| client.receive_answer(&mut lines));
+        probe_result(result).map(|_| true)
+    }
+
     /// Set the client name. It must be the first call on startup.
     pub fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<&mut Self> {
         self.send(Request::SetName(client_name))
@@ -234,6 +822,377 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::Speak)
     }
 
+    /// Speak one line of text and return the id of the queued message.
+    ///
+    /// The text is escaped according to [`Client::escape_policy`].
+    ///
+    /// If chunking is enabled and the text is longer than [`Client::max_message_bytes`], it
+    /// is queued as several messages and the id of the last one is returned. Use
+    /// [`Client::say_chunks`] to get all the ids.
+    pub fn say_line(&mut self, line: &str) -> ClientResult<MessageId> {
+        match self.say_chunks(line)?.pop() {
+            Some(id) => Ok(id),
+            None => self.say_line_escaped(line, self.escape_policy()),
+        }
+    }
+
+    /// Speak a borrowed or owned text and return the id of the queued message.
+    ///
+    /// It's the same as [`Client::say_line`], for callers that already hold a `String` or a
+    /// `Cow<str>` and don't want to clone it.
+    pub fn say_text<'t>(&mut self, text: impl Into<Cow<'t, str>>) -> ClientResult<MessageId> {
+        self.say_line(&text.into())
+    }
+
+    /// Format a value, speak it and return the id of the queued message.
+    ///
+    /// The formatted text goes through [`Client::say_line`], so it's escaped and chunked the
+    /// same way. A value formatted on several lines is spoken as one message.
+    pub fn say<T: fmt::Display>(&mut self, value: T) -> ClientResult<MessageId> {
+        self.say_line(&value.to_string())
+    }
+
+    /// Speak a text, split in several messages if it's too long, and return the ids in order.
+    ///
+    /// The text is split at sentence boundaries in chunks of at most
+    /// [`Client::max_message_bytes`] bytes before escaping, only if chunking is enabled.
+    /// Otherwise it's queued as a single message.
+    ///
+    /// If a message fails, the error is a [`ClientError::PartialBatch`] holding the ids
+    /// of the messages already queued.
+    pub fn say_chunks(&mut self, text: &str) -> ClientResult<Vec<MessageId>> {
+        let policy = self.escape_policy();
+        match self.max_message_bytes.filter(|_| self.chunking) {
+            Some(max) if text.len() > max => {
+                let mut ids = Vec::new();
+                for chunk in split_chunks(text, max) {
+                    match self.say_line_escaped(chunk, policy) {
+                        Ok(id) => ids.push(id),
+                        Err(err) => {
+                            return Err(ClientError::PartialBatch {
+                                ids,
+                                source: Box::new(err),
+                            })
+                        }
+                    }
+                }
+                Ok(ids)
+            }
+            _ => Ok(vec![self.say_line_escaped(text, policy)?]),
+        }
+    }
+
+    /// Maximum length in bytes of a message queued by [`Client::say_line`] with chunking.
+    ///
+    /// It's [`DEFAULT_MAX_MESSAGE_BYTES`] unless set, `None` if there is no limit.
+    pub fn max_message_bytes(&self) -> Option<usize> {
+        self.max_message_bytes
+    }
+
+    /// Set the maximum length of a message, `None` for no limit.
+    pub fn set_max_message_bytes(&mut self, max: Option<usize>) -> &mut Self {
+        self.max_message_bytes = max;
+        self
+    }
+
+    /// Split the texts longer than [`Client::max_message_bytes`] in several messages.
+    pub fn set_chunking(&mut self, chunking: bool) -> &mut Self {
+        self.chunking = chunking;
+        self
+    }
+
+    /// Transformation applied to the text by [`Client::say_line`] before escaping.
+    ///
+    /// The default normalizer leaves the text unchanged.
+    pub fn normalizer(&self) -> TextNormalizer {
+        self.normalizer
+    }
+
+    /// Set the transformation applied to the text before escaping.
+    pub fn set_normalizer(&mut self, normalizer: TextNormalizer) -> &mut Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Speak a text escaped with the given policy and return the id of the queued message.
+    ///
+    /// The text is first transformed by [`Client::normalizer`]. With [`EscapePolicy::None`],
+    /// a line made of a single dot is refused since it would end the text early.
+    pub fn say_line_escaped(
+        &mut self,
+        text: &str,
+        policy: EscapePolicy,
+    ) -> ClientResult<MessageId> {
+        let normalizer = self.normalizer;
+        let text = normalizer.normalize(text);
+        let text = text.as_ref();
+        match policy {
+            EscapePolicy::SsipDots => self.say_bytes(text.as_bytes()),
+            EscapePolicy::XmlEntities => self.say_bytes(escape_xml(text).as_bytes()),
+            EscapePolicy::None => {
+                let lines = text_lines(text).collect::<Vec<&str>>();
+                if lines.contains(&".") {
+                    return Err(ClientError::io_error(
+                        io::ErrorKind::InvalidInput,
+                        "line with a single dot",
+                    ));
+                }
+                self.speak()?.check_receiving_data()?;
+                write_lines(&mut self.writer.output, &lines)?;
+                write_lines(&mut self.writer.output, &["."])?;
+                self.receive_message_id()
+            }
+        }
+    }
+
+    /// Set how [`Client::say_line`] escapes the text, `None` to choose from the SSML mode.
+    pub fn set_escape_policy(&mut self, policy: Option<EscapePolicy>) -> &mut Self {
+        self.escape_policy = policy;
+        self
+    }
+
+    /// Policy applied by [`Client::say_line`].
+    ///
+    /// Unless set, it's [`EscapePolicy::XmlEntities`] in SSML mode and
+    /// [`EscapePolicy::SsipDots`] otherwise.
+    pub fn escape_policy(&self) -> EscapePolicy {
+        self.escape_policy
+            .unwrap_or_else(|| EscapePolicy::for_ssml_mode(self.ssml_mode))
+    }
+
+    /// Speak a UTF-8 text given as bytes and return the message id.
+    ///
+    /// The text is written directly to the socket, without normalization. It may contain
+    /// several lines, lines starting with a dot are escaped.
+    pub fn say_bytes(&mut self, text: &[u8]) -> ClientResult<MessageId> {
+        self.speak()?.check_receiving_data()?;
+        write_text_bytes(&mut self.writer.output, text)?;
+        self.receive_message_id()
+    }
+
+    /// Spell a text letter by letter and return the message id.
+    ///
+    /// Three commands are sent: spelling is enabled, the text is spoken and spelling is
+    /// restored to its previous state, even if speaking failed.
+    pub fn spell(&mut self, text: &str) -> ClientResult<MessageId> {
+        let previous = self.spelling;
+        self.set_spelling(ClientScope::Current, true)?
+            .check_status(OK_SPELLING_SET)?;
+        let result = self.say_line(text);
+        let restored = self
+            .set_spelling(ClientScope::Current, previous)
+            .and_then(|client| client.check_status(OK_SPELLING_SET));
+        let id = result?;
+        restored?;
+        Ok(id)
+    }
+
+    /// Speak one line of text with a priority and return the message id.
+    ///
+    /// The priority is restored to its previous value afterwards, even if speaking failed,
+    /// so that it doesn't apply to the following messages.
+    pub fn say_line_with_priority(
+        &mut self,
+        priority: Priority,
+        text: &str,
+    ) -> ClientResult<MessageId> {
+        self.with_priority(priority, |client| client.say_line(text))
+    }
+
+    /// Speak a symbolic key name with a priority and return the message id.
+    ///
+    /// Key echoes default to [`Priority::Notification`] when no priority is given. As with
+    /// [`Client::say_line_with_priority`], the previous priority is restored afterwards.
+    pub fn say_key_with_priority(
+        &mut self,
+        priority: Option<Priority>,
+        key_name: KeyName,
+    ) -> ClientResult<MessageId> {
+        self.with_priority(priority.unwrap_or(Priority::Notification), |client| {
+            client.speak_key(key_name)?.receive_message_id()
+        })
+    }
+
+    /// Speak a char with a priority and return the message id.
+    ///
+    /// Char echoes default to [`Priority::Notification`] when no priority is given. As with
+    /// [`Client::say_line_with_priority`], the previous priority is restored afterwards.
+    pub fn say_char_with_priority(
+        &mut self,
+        priority: Option<Priority>,
+        ch: char,
+    ) -> ClientResult<MessageId> {
+        self.with_priority(priority.unwrap_or(Priority::Notification), |client| {
+            client.speak_char(ch)?.receive_message_id()
+        })
+    }
+
+    /// Run `f` with a priority, then restore the previous priority even if `f` failed.
+    fn with_priority<T>(
+        &mut self,
+        priority: Priority,
+        f: impl FnOnce(&mut Self) -> ClientResult<T>,
+    ) -> ClientResult<T> {
+        let previous = self.priority;
+        self.set_priority(priority)?.check_status(OK_PRIORITY_SET)?;
+        let result = f(self);
+        let restored = self
+            .set_priority(previous)
+            .and_then(|client| client.check_status(OK_PRIORITY_SET));
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+
+    /// Speak a SSML document and return the message id.
+    ///
+    /// SSML mode is enabled for the message and restored to its previous state afterwards,
+    /// even if speaking failed.
+    pub fn say_ssml(&mut self, ssml: &str) -> ClientResult<MessageId> {
+        let previous = self.ssml_mode;
+        self.set_ssml_mode(true)?.check_status(OK_SSML_MODE_SET)?;
+        let result = self.say_bytes(ssml.as_bytes());
+        let restored = self
+            .set_ssml_mode(previous)
+            .and_then(|client| client.check_status(OK_SSML_MODE_SET));
+        let id = result?;
+        restored?;
+        Ok(id)
+    }
+
+    /// Speak a text with an index mark at the start of each word or sentence.
+    ///
+    /// Return the message id and the byte offset in the text of each mark, so that the region
+    /// being spoken can be highlighted when [`EventType::IndexMark`] events are received.
+    pub fn say_with_boundaries(
+        &mut self,
+        text: &str,
+        granularity: Boundary,
+    ) -> ClientResult<(MessageId, HashMap<String, usize>)> {
+        let mut builder = SpeechBuilder::new();
+        let mut offsets = HashMap::new();
+        let mut start = 0;
+        for (index, offset) in boundary_offsets(text, granularity).into_iter().enumerate() {
+            let name = format!("b{}", index);
+            builder.text(&text[start..offset]).mark(&name);
+            offsets.insert(name, offset);
+            start = offset;
+        }
+        builder.text(&text[start..]);
+        let (id, _) = builder.speak(self)?;
+        Ok((id, offsets))
+    }
+
+    /// Speak a text in a language and return the message id.
+    ///
+    /// SSIP has no per-message language, so the current language is read, changed for the
+    /// message and restored afterwards, even if speaking failed.
+    pub fn say_in_language(&mut self, lang: &str, text: &str) -> ClientResult<MessageId> {
+        let previous = self.get_language()?.receive_string(OK_GET)?;
+        self.set_language(ClientScope::Current, lang)?
+            .check_status(OK_LANGUAGE_SET)?;
+        let result = self.say_line(text);
+        let restored = self
+            .set_language(ClientScope::Current, &previous)
+            .and_then(|client| client.check_status(OK_LANGUAGE_SET));
+        let id = result?;
+        restored?;
+        Ok(id)
+    }
+
+    /// Speak one line of text and wait until it is spoken or canceled.
+    ///
+    /// Notifications for begin, end and cancel are enabled if they were not. Events of other
+    /// messages are discarded while waiting, so this doesn't work if the events are consumed
+    /// elsewhere, for example by the reader of a split client. If the server doesn't support
+    /// notifications, nothing is spoken and [`ClientError::NotificationsUnsupported`] is returned.
+    pub fn say_line_blocking(&mut self, text: &str) -> ClientResult<()> {
+        self.enable_notifications(&[
+            NotificationType::Begin,
+            NotificationType::End,
+            NotificationType::Cancel,
+        ])?;
+        let id = self.say_line(text)?;
+        self.wait_message_end(id)
+    }
+
+    /// Speak one line of text, waiting for room in the queue of the server if it's full.
+    ///
+    /// When the server answers [`ERR_QUEUE_FULL`] (`431`), the next end or cancel event of
+    /// any message is awaited before sending the text again.
+    /// Other errors are returned immediately, as [`Client::say_line`] does for all errors.
+    ///
+    /// Notifications for end and cancel are enabled before the first attempt if they were
+    /// not, and [`ClientError::NotificationsUnsupported`] is returned if the server refuses
+    /// them. Only the messages of this client send events: if the queue is filled by other
+    /// clients, this waits until one of its own messages ends, possibly forever.
+    pub fn say_line_when_ready(&mut self, text: &str) -> ClientResult<MessageId> {
+        self.enable_notifications(&[NotificationType::End, NotificationType::Cancel])?;
+        loop {
+            match self.say_line(text) {
+                Err(ClientError::Ssip(status)) if status.code == ERR_QUEUE_FULL => {
+                    self.wait_any_message_end()?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Read events until a message is spoken or canceled.
+    fn wait_any_message_end(&mut self) -> ClientResult<()> {
+        loop {
+            let event = self.receive_event()?;
+            if matches!(event.ntype, EventType::End | EventType::Cancel) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Enable the notifications that were not enabled yet.
+    fn enable_notifications(&mut self, ntypes: &[NotificationType]) -> ClientResult<()> {
+        for ntype in ntypes {
+            if !self.notifications.contains(&NotificationType::All)
+                && !self.notifications.contains(ntype)
+            {
+                self.set_notification_checked(*ntype, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read events until the message is spoken or canceled.
+    fn wait_message_end(&mut self, id: MessageId) -> ClientResult<()> {
+        loop {
+            let event = self.receive_event()?;
+            if event.matches(&id) && matches!(event.ntype, EventType::End | EventType::Cancel) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Speak each line as a separate message and return the message ids in order.
+    ///
+    /// If a message fails, the error is a [`ClientError::PartialBatch`] holding the ids
+    /// of the messages already queued.
+    pub fn say_all<'a>(
+        &mut self,
+        lines: impl IntoIterator<Item = &'a str>,
+    ) -> ClientResult<Vec<MessageId>> {
+        let mut ids = Vec::new();
+        for line in lines {
+            match self.say_line(line) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    return Err(ClientError::PartialBatch {
+                        ids,
+                        source: Box::new(err),
+                    })
+                }
+            }
+        }
+        Ok(ids)
+    }
+
     /// Speak a char
     pub fn speak_char(&mut self, ch: char) -> ClientResult<&mut Self> {
         self.send(Request::SpeakChar(ch))
@@ -245,23 +1204,165 @@ impl<S: Read + Write + Source> Client<S> {
     }
 
     /// Stop current message
-    pub fn stop(&mut self, scope: MessageScope) -> ClientResult<&mut Self> {
-        self.send(Request::Stop(scope))
+    ///
+    /// A number in scope is a message id, see [`Client::stop_client`] to target a client.
+    pub fn stop(&mut self, scope: impl Into<MessageScope>) -> ClientResult<&mut Self> {
+        self.send(Request::Stop(scope.into()))
+    }
+
+    /// Stop several messages and return the status of each `STOP`, in the order of the ids.
+    ///
+    /// Errors returned by the server for an id are collected and don't prevent stopping the
+    /// next ones: an id that doesn't exist, for example because the message is already
+    /// finished, gives [`ClientError::UnknownId`] at its position. Other errors, such as a
+    /// lost connection, are returned at once.
+    pub fn stop_many(&mut self, ids: &[MessageId]) -> ClientResult<Vec<ClientStatus>> {
+        let mut statuses = Vec::with_capacity(ids.len());
+        for id in ids {
+            let mut lines = Vec::new();
+            match self
+                .stop(MessageScope::Message(*id))
+                .and_then(|client| client.receive_answer(&mut lines))
+            {
+                Err(err @ (ClientError::Ssip(_) | ClientError::UnknownId { .. })) => {
+                    statuses.push(Err(err))
+                }
+                Err(err) => return Err(err),
+                Ok(status) => statuses.push(Ok(status)),
+            }
+        }
+        Ok(statuses)
     }
 
     /// Cancel current message
-    pub fn cancel(&mut self, scope: MessageScope) -> ClientResult<&mut Self> {
-        self.send(Request::Cancel(scope))
+    pub fn cancel(&mut self, scope: impl Into<MessageScope>) -> ClientResult<&mut Self> {
+        self.send(Request::Cancel(scope.into()))
+    }
+
+    /// Stop the messages of the clients in scope
+    pub fn stop_client(&mut self, scope: impl Into<ClientScope>) -> ClientResult<&mut Self> {
+        self.send(Request::StopClient(scope.into()))
+    }
+
+    /// Cancel the messages of the clients in scope, including queued ones
+    pub fn cancel_client(&mut self, scope: impl Into<ClientScope>) -> ClientResult<&mut Self> {
+        self.send(Request::CancelClient(scope.into()))
+    }
+
+    /// Pause the messages of the clients in scope
+    pub fn pause_client(&mut self, scope: impl Into<ClientScope>) -> ClientResult<&mut Self> {
+        self.send(Request::PauseClient(scope.into()))
+    }
+
+    /// Resume the messages of the clients in scope
+    pub fn resume_client(&mut self, scope: impl Into<ClientScope>) -> ClientResult<&mut Self> {
+        self.send(Request::ResumeClient(scope.into()))
     }
 
     /// Pause current message
-    pub fn pause(&mut self, scope: MessageScope) -> ClientResult<&mut Self> {
-        self.send(Request::Pause(scope))
+    pub fn pause(&mut self, scope: impl Into<MessageScope>) -> ClientResult<&mut Self> {
+        self.send(Request::Pause(scope.into()))
     }
 
     /// Resume current message
-    pub fn resume(&mut self, scope: MessageScope) -> ClientResult<&mut Self> {
-        self.send(Request::Resume(scope))
+    pub fn resume(&mut self, scope: impl Into<MessageScope>) -> ClientResult<&mut Self> {
+        self.send(Request::Resume(scope.into()))
+    }
+
+    /// Set the pause context of the client and pause in one round trip.
+    ///
+    /// On resume, the server repeats about `context` sentences before the point where the
+    /// text was paused. The previous context is saved so that
+    /// [`Client::resume_with_context`] can restore it. Both answers are checked, a failure
+    /// is reported as [`ClientError::PipelineFailed`].
+    pub fn pause_with_context(
+        &mut self,
+        scope: impl Into<MessageScope>,
+        context: u32,
+    ) -> ClientResult<&mut Self> {
+        let previous = self.pause_context;
+        self.pipeline()
+            .push(Request::SetPauseContext(ClientScope::Current, context))
+            .push(Request::Pause(scope.into()))
+            .execute()?;
+        self.saved_pause_context = Some(previous);
+        Ok(self)
+    }
+
+    /// Resume and check the answer, restoring the context saved by
+    /// [`Client::pause_with_context`] if `restore` is true.
+    ///
+    /// Without restoring, the context stays in effect for the next pauses.
+    pub fn resume_with_context(
+        &mut self,
+        scope: impl Into<MessageScope>,
+        restore: bool,
+    ) -> ClientResult<&mut Self> {
+        self.resume(scope)?.check_status(OK_RESUMED)?;
+        match self.saved_pause_context.take() {
+            Some(context) if restore => self
+                .set_pause_context(ClientScope::Current, context)?
+                .check_status(OK_PAUSE_CONTEXT_SET),
+            _ => Ok(self),
+        }
+    }
+
+    /// Reset rate, pitch, volume, punctuation and spelling to the defaults of Speech Dispatcher.
+    ///
+    /// The requests are sent in one batch and all the answers are read. The first failure is
+    /// returned, otherwise the status of the last request.
+    pub fn reset_parameters(&mut self) -> ClientStatus {
+        let requests = [
+            (
+                Request::SetRate(ClientScope::Current, Rate::default()),
+                OK_RATE_SET,
+            ),
+            (
+                Request::SetPitch(ClientScope::Current, Pitch::default()),
+                OK_PITCH_SET,
+            ),
+            (
+                Request::SetVolume(ClientScope::Current, Volume::default()),
+                OK_VOLUME_SET,
+            ),
+            (
+                Request::SetPunctuationMode(ClientScope::Current, PunctuationMode::None),
+                OK_PUNCTUATION_SET,
+            ),
+            (
+                Request::SetSpelling(ClientScope::Current, false),
+                OK_SPELLING_SET,
+            ),
+        ];
+        let expected_codes = requests
+            .iter()
+            .map(|(_, code)| *code)
+            .collect::<Vec<ReturnCode>>();
+        for (request, _) in requests {
+            self.send(request)?;
+        }
+        let mut first_error = None;
+        let mut last_status = None;
+        for expected_code in expected_codes {
+            let mut lines = Vec::new();
+            match self.receive_answer(&mut lines) {
+                Ok(status) if status.code == expected_code => last_status = Some(status),
+                Ok(status) => {
+                    first_error.get_or_insert(ClientError::UnexpectedStatus(status.code));
+                }
+                Err(err @ (ClientError::Ssip(_) | ClientError::UnknownId { .. })) => {
+                    first_error.get_or_insert(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => last_status.ok_or(ClientError::TooFewLines {
+                expected: 1,
+                got: 0,
+            }),
+        }
     }
 
     /// Set message priority
@@ -269,18 +1370,63 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::SetPriority(prio))
     }
 
-    /// Set debug mode. Return the log location
-    pub fn set_debug(&mut self, value: bool) -> ClientResult<&mut Self> {
-        self.send(Request::SetDebug(value))
+    /// Set the priority of the following messages and check the answer.
+    ///
+    /// Priority is sticky on the server: it applies to every message sent on this
+    /// connection until it is changed again. The client remembers it, see [`Client::priority`].
+    pub fn set_default_priority(&mut self, prio: Priority) -> ClientResult<&mut Self> {
+        self.set_priority(prio)?.check_status(OK_PRIORITY_SET)
+    }
+
+    /// Last priority sent to the server, [`Priority::Text`] if none was sent.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Set debug mode.
+    ///
+    /// When enabling, return the directory where the server writes its debug logs.
+    /// When disabling, return `None`.
+    pub fn set_debug(&mut self, value: bool) -> ClientResult<Option<PathBuf>> {
+        self.send(Request::SetDebug(value))?;
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines)?;
+        if status.code != OK_DEBUG_SET {
+            Err(ClientError::UnexpectedStatus(status.code))
+        } else if value {
+            Ok(parse_debug_path(&status, &lines))
+        } else {
+            Ok(None)
+        }
     }
 
     /// Set output module
     pub fn set_output_module(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         value: &str,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::SetOutputModule(scope, value.to_string()))
+        self.send(Request::SetOutputModule(scope.into(), value.to_string()))
+    }
+
+    /// Set the output module whose name matches ignoring case
+    ///
+    /// The output modules are listed to find the exact name, which is returned. If no module
+    /// matches, the error is [`ClientError::InvalidType`]. Use [`Client::set_output_module`]
+    /// when the exact name is known.
+    pub fn set_output_module_fuzzy(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        name: &str,
+    ) -> ClientResult<String> {
+        let module = self
+            .output_modules()?
+            .into_iter()
+            .find(|module| module.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ClientError::InvalidType(format!("no output module {}", name)))?;
+        self.set_output_module(scope, &module)?
+            .check_status(OK_OUTPUT_MODULE_SET)?;
+        Ok(module)
     }
 
     /// Get the current output module
@@ -294,8 +1440,12 @@ impl<S: Read + Write + Source> Client<S> {
     }
 
     /// Set language code
-    pub fn set_language(&mut self, scope: ClientScope, value: &str) -> ClientResult<&mut Self> {
-        self.send(Request::SetLanguage(scope, value.to_string()))
+    pub fn set_language(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: &str,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetLanguage(scope.into(), value.to_string()))
     }
 
     /// Get the current language
@@ -311,29 +1461,84 @@ impl<S: Read + Write + Source> Client<S> {
     /// Set punctuation mode
     pub fn set_punctuation_mode(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         mode: PunctuationMode,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::SetPunctuationMode(scope, mode))
+        self.send(Request::SetPunctuationMode(scope.into(), mode))
     }
 
     /// Set spelling on or off
-    pub fn set_spelling(&mut self, scope: ClientScope, value: bool) -> ClientResult<&mut Self> {
-        self.send(Request::SetSpelling(scope, value))
+    pub fn set_spelling(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: bool,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetSpelling(scope.into(), value))
+    }
+
+    /// Set the synthesis voice that best matches a language and return it
+    ///
+    /// The language is a tag such as `de` or `de-AT`. A voice with the exact tag is preferred,
+    /// otherwise the first voice with the same primary language is chosen. If no voice
+    /// matches, the error is [`ClientError::InvalidType`].
+    pub fn set_voice_for_language(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        lang: &str,
+    ) -> ClientResult<SynthesisVoice> {
+        let voice = self
+            .synthesis_voices()?
+            .into_iter()
+            .filter_map(|voice| voice_language_rank(&voice, lang).map(|rank| (rank, voice)))
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, voice)| voice)
+            .ok_or_else(|| ClientError::InvalidType(format!("no voice for language {}", lang)))?;
+        self.set_synthesis_voice(scope, &voice.name)?
+            .check_status(OK_VOICE_SET)?;
+        Ok(voice)
+    }
+
+    /// Set the first available synthesis voice among the preferred names and return it
+    ///
+    /// Names are compared exactly with the voices listed by the server, in the order of
+    /// preference. If none is available, the error is [`ClientError::InvalidType`].
+    pub fn set_synthesis_voice_or_fallback(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        preferred: &[&str],
+    ) -> ClientResult<SynthesisVoice> {
+        let mut voices = self.synthesis_voices()?;
+        let position = preferred
+            .iter()
+            .find_map(|name| voices.iter().position(|voice| voice.name == *name))
+            .ok_or_else(|| {
+                ClientError::InvalidType(format!("no voice among {}", preferred.join(", ")))
+            })?;
+        let voice = voices.swap_remove(position);
+        self.set_synthesis_voice(scope, &voice.name)?
+            .check_status(OK_VOICE_SET)?;
+        Ok(voice)
     }
 
     /// Set capital letters recognition mode
     pub fn set_capital_letter_recogn(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         mode: CapitalLettersRecognitionMode,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::SetCapitalLettersRecognitionMode(scope, mode))
+        self.send(Request::SetCapitalLettersRecognitionMode(
+            scope.into(),
+            mode,
+        ))
     }
 
     /// Set the voice type (MALE1, FEMALE1, …)
-    pub fn set_voice_type(&mut self, scope: ClientScope, value: &str) -> ClientResult<&mut Self> {
-        self.send(Request::SetVoiceType(scope, value.to_string()))
+    pub fn set_voice_type(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: &str,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetVoiceType(scope.into(), value.to_string()))
     }
 
     /// Get the current pre-defined voice
@@ -346,13 +1551,47 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::ListVoiceTypes)
     }
 
+    /// Return the symbolic voice names exposed by the current output module
+    ///
+    /// Unlike [`Client::list_synthesis_voices`], these are voice types such as `MALE1`.
+    pub fn list_voices(&mut self) -> ClientResult<Vec<String>> {
+        self.list(Request::ListVoiceTypes)
+    }
+
+    /// Return the names of the available output modules
+    pub fn output_modules(&mut self) -> ClientResult<Vec<String>> {
+        self.list(Request::ListOutputModules)
+    }
+
+    /// Return the voices of the current synthesizer
+    pub fn synthesis_voices(&mut self) -> ClientResult<Vec<SynthesisVoice>> {
+        self.list(Request::ListSynthesisVoices)
+    }
+
+    /// Send a `LIST` request and parse each line of the answer.
+    ///
+    /// An answer without data lines is an empty list.
+    fn list<T>(&mut self, request: Request) -> ClientResult<Vec<T>>
+    where
+        T: FromStr,
+        ClientError: From<T::Err>,
+    {
+        let expected_code = match request {
+            Request::ListOutputModules => OK_OUTPUT_MODULES_LIST_SENT,
+            _ => OK_VOICES_LIST_SENT,
+        };
+        self.send(request)?
+            .receive_lines(expected_code)
+            .and_then(|lines| parse_typed_lines::<T>(&lines))
+    }
+
     /// Set the voice
     pub fn set_synthesis_voice(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         value: &str,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::SetSynthesisVoice(scope, value.to_string()))
+        self.send(Request::SetSynthesisVoice(scope.into(), value.to_string()))
     }
 
     /// Lists the available voices for the current synthesizer
@@ -360,9 +1599,13 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::ListSynthesisVoices)
     }
 
-    /// Set the rate of speech. n is an integer value within the range from -100 to 100, lower values meaning slower speech.
-    pub fn set_rate(&mut self, scope: ClientScope, value: i8) -> ClientResult<&mut Self> {
-        self.send(Request::SetRate(scope, value))
+    /// Set the rate of speech. Lower values mean slower speech.
+    pub fn set_rate(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: Rate,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetRate(scope.into(), value))
     }
 
     /// Get the current rate of speech.
@@ -370,9 +1613,13 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::GetRate)
     }
 
-    /// Set the pitch of speech. n is an integer value within the range from -100 to 100.
-    pub fn set_pitch(&mut self, scope: ClientScope, value: i8) -> ClientResult<&mut Self> {
-        self.send(Request::SetPitch(scope, value))
+    /// Set the pitch of speech.
+    pub fn set_pitch(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: Pitch,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetPitch(scope.into(), value))
     }
 
     /// Get the current pitch value.
@@ -380,9 +1627,27 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::GetPitch)
     }
 
-    /// Set the volume of speech. n is an integer value within the range from -100 to 100.
-    pub fn set_volume(&mut self, scope: ClientScope, value: i8) -> ClientResult<&mut Self> {
-        self.send(Request::SetVolume(scope, value))
+    /// Set the pitch range of speech.
+    pub fn set_pitch_range(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: PitchRange,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetPitchRange(scope.into(), value))
+    }
+
+    /// Get the current pitch range value.
+    pub fn get_pitch_range(&mut self) -> ClientResult<&mut Self> {
+        self.send(Request::GetPitchRange)
+    }
+
+    /// Set the volume of speech.
+    pub fn set_volume(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: Volume,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetVolume(scope.into(), value))
     }
 
     /// Get the current volume.
@@ -390,33 +1655,210 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::GetVolume)
     }
 
+    /// Read the current speech parameters.
+    ///
+    /// One `GET` is sent per parameter. A parameter that the server refuses to return is
+    /// left to `None` instead of failing. Speech Dispatcher doesn't support `GET PUNCTUATION`
+    /// for instance, so the punctuation mode is usually `None`.
+    pub fn get_settings(&mut self) -> ClientResult<Settings> {
+        let rate = self.get_setting(Request::GetRate)?;
+        let pitch = self.get_setting(Request::GetPitch)?;
+        let volume = self.get_setting(Request::GetVolume)?;
+        Ok(Settings {
+            rate: rate.as_deref().map(parse_speech_parameter).transpose()?,
+            pitch: pitch.as_deref().map(parse_speech_parameter).transpose()?,
+            volume: volume.as_deref().map(parse_speech_parameter).transpose()?,
+            voice: self.get_setting(Request::GetVoiceType)?,
+            language: self.get_setting(Request::GetLanguage)?,
+            punctuation: self
+                .get_setting(Request::GetPunctuationMode)?
+                .as_deref()
+                .map(str::parse)
+                .transpose()?,
+        })
+    }
+
+    /// Set the speech parameters that are not `None`.
+    ///
+    /// All parameters are sent even if some are refused. The refused ones are reported
+    /// together in [`ClientError::SettingsFailed`]. Other errors, such as I/O errors, are
+    /// returned immediately.
+    pub fn apply_settings(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        settings: &Settings,
+    ) -> ClientResult<()> {
+        let scope = scope.into();
+        let mut failures = Vec::new();
+        let mut apply = |name: &'static str, result: ClientResult<&mut Self>| match result {
+            Ok(_) => Ok(()),
+            Err(err @ (ClientError::Ssip(_) | ClientError::UnknownId { .. })) => {
+                failures.push((name, err));
+                Ok(())
+            }
+            Err(err) => Err(err),
+        };
+        if let Some(rate) = settings.rate {
+            apply(
+                "rate",
+                self.set_rate(scope, rate)
+                    .and_then(|client| client.check_status(OK_RATE_SET)),
+            )?;
+        }
+        if let Some(pitch) = settings.pitch {
+            apply(
+                "pitch",
+                self.set_pitch(scope, pitch)
+                    .and_then(|client| client.check_status(OK_PITCH_SET)),
+            )?;
+        }
+        if let Some(volume) = settings.volume {
+            apply(
+                "volume",
+                self.set_volume(scope, volume)
+                    .and_then(|client| client.check_status(OK_VOLUME_SET)),
+            )?;
+        }
+        if let Some(voice) = &settings.voice {
+            apply(
+                "voice",
+                self.set_voice_type(scope, voice)
+                    .and_then(|client| client.check_status(OK_VOICE_SET)),
+            )?;
+        }
+        if let Some(language) = &settings.language {
+            apply(
+                "language",
+                self.set_language(scope, language)
+                    .and_then(|client| client.check_status(OK_LANGUAGE_SET)),
+            )?;
+        }
+        if let Some(mode) = settings.punctuation {
+            apply(
+                "punctuation",
+                self.set_punctuation_mode(scope, mode)
+                    .and_then(|client| client.check_status(OK_PUNCTUATION_SET)),
+            )?;
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ClientError::SettingsFailed { failures })
+        }
+    }
+
+    /// Validate a profile and apply its settings and voice to this client.
+    ///
+    /// The client name of the profile is not sent since it can only be set when connecting.
+    pub fn apply_profile(&mut self, profile: &Profile) -> ClientResult<()> {
+        profile.validate()?;
+        self.apply_settings(ClientScope::Current, &profile.settings)?;
+        self.set_synthesis_voice(ClientScope::Current, &profile.voice.name)?
+            .check_status(OK_VOICE_SET)?;
+        Ok(())
+    }
+
+    /// Set the speech parameters of this client that are not `None` inside a block.
+    ///
+    /// Same as [`Client::apply_settings`] with [`ClientScope::Current`], but the requests are
+    /// sent between `BLOCK BEGIN` and `BLOCK END`. The block is closed even if a parameter
+    /// is refused.
+    pub fn apply_settings_in_block(&mut self, settings: &Settings) -> ClientResult<()> {
+        self.block_begin()?.check_status(OK_INSIDE_BLOCK)?;
+        let result = self.apply_settings(ClientScope::Current, settings);
+        let closed = self
+            .block_end()
+            .and_then(|client| client.check_status(OK_OUTSIDE_BLOCK));
+        result?;
+        closed?;
+        Ok(())
+    }
+
+    /// Send a `GET` request and return the value, `None` if the server returned an error.
+    fn get_setting(&mut self, request: Request) -> ClientResult<Option<String>> {
+        match self.send(request)?.receive_string(OK_GET) {
+            Ok(value) => Ok(Some(value)),
+            Err(ClientError::Ssip(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Set the number of (more or less) sentences that should be repeated after a previously paused text is resumed.
-    pub fn set_pause_context(&mut self, scope: ClientScope, value: u32) -> ClientResult<&mut Self> {
-        self.send(Request::SetPauseContext(scope, value))
+    pub fn set_pause_context(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: u32,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetPauseContext(scope.into(), value))
     }
 
     /// Enable notification events
+    ///
+    /// Use [`Client::set_notification_checked`] to detect servers without notifications.
     pub fn set_notification(
         &mut self,
         ntype: NotificationType,
         value: bool,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::SetNotification(ntype, value))
+        self.send(Request::SetNotification(ntype, value))
+    }
+
+    /// Enable or disable notification events and check the answer.
+    ///
+    /// An error `4xx` returned by the server is reported as
+    /// [`ClientError::NotificationsUnsupported`] so that callers can fall back to polling
+    /// instead of waiting for events that will never come.
+    pub fn set_notification_checked(
+        &mut self,
+        ntype: NotificationType,
+        value: bool,
+    ) -> ClientResult<&mut Self> {
+        match self
+            .set_notification(ntype, value)
+            .and_then(|client| client.check_status(OK_NOTIFICATION_SET))
+        {
+            Ok(_) => Ok(self),
+            Err(ClientError::Ssip(status)) if (400..500).contains(&status.code) => {
+                if value {
+                    self.notifications.remove(&ntype);
+                }
+                Err(ClientError::NotificationsUnsupported(status))
+            }
+            Err(err) => Err(err),
+        }
     }
 
     /// Open a block
+    ///
+    /// Requests are buffered until the block is closed or a response is read.
     pub fn block_begin(&mut self) -> ClientResult<&mut Self> {
         self.send(Request::Begin)
     }
 
-    /// End a block
+    /// End a block and send the buffered requests
     pub fn block_end(&mut self) -> ClientResult<&mut Self> {
         self.send(Request::End)
     }
 
     /// Enable or disable history of received messages.
-    pub fn set_history(&mut self, scope: ClientScope, value: bool) -> ClientResult<&mut Self> {
-        self.send(Request::SetHistory(scope, value))
+    pub fn set_history(
+        &mut self,
+        scope: impl Into<ClientScope>,
+        value: bool,
+    ) -> ClientResult<&mut Self> {
+        self.send(Request::SetHistory(scope.into(), value))
+    }
+
+    /// Return the client id, querying the server if it wasn't sent with the `CLIENT_NAME` reply.
+    pub fn get_client_id(&mut self) -> ClientResult<ClientId> {
+        match self.client_id {
+            Some(id) => Ok(id),
+            None => {
+                let id = self.history_get_client_id()?.receive_client_id()?;
+                self.client_id = Some(id);
+                Ok(id)
+            }
+        }
     }
 
     /// Get clients in history.
@@ -437,11 +1879,11 @@ impl<S: Read + Write + Source> Client<S> {
     /// Get a range of client messages.
     pub fn history_get_client_messages(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         start: u32,
         number: u32,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::HistoryGetClientMsgs(scope, start, number))
+        self.send(Request::HistoryGetClientMsgs(scope.into(), start, number))
     }
 
     /// Get the id of the last message sent by the client.
@@ -462,10 +1904,10 @@ impl<S: Read + Write + Source> Client<S> {
     /// Set the history cursor position.
     pub fn history_set_cursor(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         pos: HistoryPosition,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::HistoryCursorSet(scope, pos))
+        self.send(Request::HistoryCursorSet(scope.into(), pos))
     }
 
     /// Move the cursor position backward or forward.
@@ -500,10 +1942,56 @@ impl<S: Read + Write + Source> Client<S> {
     /// Search in message history.
     pub fn history_search(
         &mut self,
-        scope: ClientScope,
+        scope: impl Into<ClientScope>,
         condition: &str,
     ) -> ClientResult<&mut Self> {
-        self.send(Request::HistorySearch(scope, condition.to_string()))
+        self.send(Request::HistorySearch(scope.into(), condition.to_string()))
+    }
+
+    /// List the commands understood by the server
+    pub fn help(&mut self) -> ClientResult<&mut Self> {
+        self.send(Request::Help)
+    }
+
+    /// Return the help lines of a command, such as `SET`.
+    ///
+    /// Servers without help for the command answer with [`ERR_INVALID_COMMAND`], that is
+    /// returned as an empty list. Other errors are returned. The command must be a single word.
+    pub fn help_for(&mut self, command: &str) -> ClientResult<Vec<String>> {
+        if command.is_empty() || command.contains(char::is_whitespace) {
+            return Err(ClientError::io_error(
+                io::ErrorKind::InvalidInput,
+                "command is not a single word",
+            ));
+        }
+        self.write_command(&format!("HELP {}", command))?;
+        let mut lines = Vec::new();
+        match self.receive_answer(&mut lines) {
+            Ok(status) if status.code == OK_HELP_SENT => Ok(lines),
+            Ok(status) => Err(ClientError::UnexpectedStatus(status.code)),
+            Err(ClientError::Ssip(status)) if status.code == ERR_INVALID_COMMAND => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Version of the protocol announced by the server as `(major, minor)`.
+    ///
+    /// The version is looked up in the answer to `HELP` the first time and cached. Servers
+    /// that don't announce a version make it fail with [`std::io::ErrorKind::InvalidData`].
+    ///
+    /// Use it to warn about an old server. The client itself doesn't adapt to the version:
+    /// the commands it sends are written the same way by all the versions of the protocol,
+    /// and optional features are detected from the answers, for example with
+    /// [`ClientError::NotificationsUnsupported`].
+    pub fn protocol_version(&mut self) -> ClientResult<(u16, u16)> {
+        if let Some(version) = self.protocol_version {
+            return Ok(version);
+        }
+        let lines = self.help()?.receive_lines(OK_HELP_SENT)?;
+        let version = parse_protocol_version(&lines)
+            .ok_or_else(|| ClientError::invalid_data("protocol version not found"))?;
+        self.protocol_version = Some(version);
+        Ok(version)
     }
 
     /// Close the connection
@@ -511,93 +1999,165 @@ impl<S: Read + Write + Source> Client<S> {
         self.send(Request::Quit)
     }
 
-    /// Receive answer from server
+    /// Enable all notifications and read events in a background thread.
+    ///
+    /// Events are forwarded to the returned receiver. Commands are sent with the returned
+    /// [`EventClient`] which also receives their answers. The thread stops when the connection
+    /// is closed, after [`EventClient::quit`] or when the handle is dropped.
+    ///
+    /// Fails with [`ClientError::NotificationsUnsupported`] if the server rejects them. The
+    /// client is consumed in any case, so call [`Client::set_notification_checked`] first to
+    /// keep it for a fallback.
+    pub fn subscribe_events(mut self) -> ClientResult<(EventClient<S>, mpsc::Receiver<Event>)>
+    where
+        S: Send + 'static,
+    {
+        self.set_notification_checked(NotificationType::All, true)?;
+        let (mut reader, writer) = self.split();
+        let (event_sender, events) = mpsc::channel();
+        let (response_sender, responses) = mpsc::channel();
+        let thread = thread::spawn(move || loop {
+            match reader.receive() {
+                Ok(response) => match response_event(response) {
+                    Ok(event) => {
+                        // The receiver of events may be dropped while commands are still sent.
+                        let _ = event_sender.send(event);
+                    }
+                    Err(response) => {
+                        if response_sender.send(Ok(response)).is_err() {
+                            break;
+                        }
+                    }
+                },
+                Err(err) => {
+                    // Errors returned by the server don't end the connection.
+                    let fatal =
+                        !matches!(err, ClientError::Ssip(_) | ClientError::UnknownId { .. });
+                    if response_sender.send(Err(err)).is_err() || fatal {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok((
+            EventClient {
+                writer,
+                responses,
+                thread: Some(thread),
+                quit_sent: false,
+            },
+            events,
+        ))
+    }
+
+    /// Split the client into a reader of responses and events and a writer of requests.
+    ///
+    /// The halves can be moved to different threads, so that events are read continuously
+    /// while commands are sent. Responses and events are both returned by [`ClientReader::receive`].
+    /// The writer takes over sending `QUIT` on drop.
+    pub fn split(self) -> (ClientReader<S>, ClientWriter<S>) {
+        let Client {
+            input,
+            partial,
+            writer,
+            events,
+            ..
+        } = self;
+        (
+            ClientReader {
+                input,
+                partial,
+                events,
+            },
+            writer,
+        )
+    }
+
+    /// Unwrap the client and return the underlying stream without sending `QUIT`.
+    ///
+    /// Pending output is flushed. Data already read from the socket but not consumed is lost.
+    pub fn into_inner(self) -> ClientResult<S> {
+        let (reader, mut writer) = self.split();
+        writer.forget_quit().output.flush()?;
+        Ok(reader.input.into_inner())
+    }
+
+    /// Don't send `QUIT` when the client is dropped.
+    ///
+    /// Useful when the connection is intentionally kept open beyond the client.
+    pub fn forget_quit(&mut self) -> &mut Self {
+        self.writer.quit_on_drop = false;
+        self
+    }
+
+    /// Receive the answer to a command from server.
+    ///
+    /// Notifications received before are queued, see [`Client::drain_events`].
     fn receive_answer(&mut self, lines: &mut Vec<String>) -> ClientStatus {
-        crate::protocol::receive_answer(&mut self.input, Some(lines))
+        self.writer.output.flush()?;
+        self.discard_pending_answers()?;
+        match self.read_status(lines) {
+            Err(ClientError::Ssip(status)) => Err(unknown_id(status, self.target.take())),
+            result => result,
+        }
+    }
+
+    /// Read the next status line, queuing the events received before.
+    fn read_status(&mut self, lines: &mut Vec<String>) -> ClientStatus {
+        loop {
+            let status = receive_answer(&mut self.input, &mut self.partial, Some(lines))?;
+            if !(EVENT_INDEX_MARK..=EVENT_RESUMED).contains(&status.code) {
+                return Ok(status);
+            }
+            let event = parse_event(&status, lines)?;
+            self.queue_event(event);
+            lines.clear();
+        }
+    }
+
+    /// Queue an event received while waiting for the answer to a command.
+    fn queue_event(&mut self, event: Event) {
+        self.messages.update(&event.id.message, &event.ntype);
+        self.events.push_back(event);
+    }
+
+    /// Notifications received while waiting for the answer to a command.
+    ///
+    /// They are also returned first by [`Client::receive_event`] and [`Client::receive`].
+    pub fn drain_events(&mut self) -> Vec<Event> {
+        self.events.drain(..).collect()
     }
 
     /// Receive one response.
     pub fn receive(&mut self) -> ClientResult<Response> {
-        const MSG_CURSOR_SET_FIRST: &str = "OK CURSOR SET FIRST";
-        let mut lines = Vec::new();
-        let status = self.receive_answer(&mut lines)?;
-        match status.code {
-            OK_LANGUAGE_SET => Ok(Response::LanguageSet),
-            OK_PRIORITY_SET => Ok(Response::PrioritySet),
-            OK_RATE_SET => Ok(Response::RateSet),
-            OK_PITCH_SET => Ok(Response::PitchSet),
-            OK_PUNCTUATION_SET => Ok(Response::PunctuationSet),
-            OK_CAP_LET_RECOGN_SET => Ok(Response::CapLetRecognSet),
-            OK_SPELLING_SET => Ok(Response::SpellingSet),
-            OK_CLIENT_NAME_SET => Ok(Response::ClientNameSet),
-            OK_VOICE_SET => Ok(Response::VoiceSet),
-            OK_STOPPED => Ok(Response::Stopped),
-            OK_PAUSED => Ok(Response::Paused),
-            OK_RESUMED => Ok(Response::Resumed),
-            OK_CANCELED => Ok(Response::Canceled),
-            OK_TABLE_SET => Ok(Response::TableSet),
-            OK_OUTPUT_MODULE_SET => Ok(Response::OutputModuleSet),
-            OK_PAUSE_CONTEXT_SET => Ok(Response::PauseContextSet),
-            OK_VOLUME_SET => Ok(Response::VolumeSet),
-            OK_SSML_MODE_SET => Ok(Response::SsmlModeSet),
-            // Warning OK_CUR_SET_FIRST == OK_NOTIFICATION_SET == 220. Matching message to make the difference
-            OK_NOTIFICATION_SET => {
-                if status.message == MSG_CURSOR_SET_FIRST {
-                    //OK_CUR_SET_FIRST => Ok(Response::HistoryCurSetFirst)
-                    Ok(Response::HistoryCurSetFirst)
-                } else {
-                    Ok(Response::NotificationSet)
-                }
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event_response(event));
+        }
+        self.writer.output.flush()?;
+        self.discard_pending_answers()?;
+        let response = read_response(&mut self.input, &mut self.partial)?;
+        self.messages.update_from_response(&response);
+        Ok(response)
+    }
+
+    /// Receive the next response that is not an event, queuing the events received before.
+    fn receive_reply(&mut self) -> ClientResult<Response> {
+        self.writer.output.flush()?;
+        self.discard_pending_answers()?;
+        loop {
+            let response = read_response(&mut self.input, &mut self.partial)?;
+            self.messages.update_from_response(&response);
+            match response_event(response) {
+                Ok(event) => self.events.push_back(event),
+                Err(response) => return Ok(response),
             }
-            OK_CUR_SET_LAST => Ok(Response::HistoryCurSetLast),
-            OK_CUR_SET_POS => Ok(Response::HistoryCurSetPos),
-            OK_PITCH_RANGE_SET => Ok(Response::PitchRangeSet),
-            OK_DEBUG_SET => Ok(Response::DebugSet),
-            OK_CUR_MOV_FOR => Ok(Response::HistoryCurMoveFor),
-            OK_CUR_MOV_BACK => Ok(Response::HistoryCurMoveBack),
-            OK_MESSAGE_QUEUED => Ok(Response::MessageQueued),
-            OK_SND_ICON_QUEUED => Ok(Response::SoundIconQueued),
-            OK_MSG_CANCELED => Ok(Response::MessageCanceled),
-            OK_RECEIVING_DATA => Ok(Response::ReceivingData),
-            OK_BYE => Ok(Response::Bye),
-            OK_CLIENTS_LIST_SENT => Ok(Response::HistoryClientListSent(parse_typed_lines::<
-                HistoryClientStatus,
-            >(&lines)?)),
-            OK_MSGS_LIST_SENT => Ok(Response::HistoryMsgsListSent(lines)),
-            OK_LAST_MSG => Ok(Response::HistoryLastMsg(parse_single_value(&lines)?)),
-            OK_CUR_POS_RET => Ok(Response::HistoryCurPosRet(parse_single_value(&lines)?)),
-            OK_TABLE_LIST_SENT => Ok(Response::TableListSent(lines)),
-            OK_CLIENT_ID_SENT => Ok(Response::HistoryClientIdSent(parse_single_integer(&lines)?)),
-            OK_MSG_TEXT_SENT => Ok(Response::MessageTextSent),
-            OK_HELP_SENT => Ok(Response::HelpSent(lines)),
-            OK_VOICES_LIST_SENT => Ok(Response::VoicesListSent(
-                parse_typed_lines::<SynthesisVoice>(&lines)?,
-            )),
-            OK_OUTPUT_MODULES_LIST_SENT => Ok(Response::OutputModulesListSent(lines)),
-            OK_GET => Ok(Response::Get(parse_single_value(&lines)?)),
-            OK_INSIDE_BLOCK => Ok(Response::InsideBlock),
-            OK_OUTSIDE_BLOCK => Ok(Response::OutsideBlock),
-            OK_NOT_IMPLEMENTED => Ok(Response::NotImplemented),
-            EVENT_INDEX_MARK => match lines.len() {
-                0..=2 => Err(ClientError::TooFewLines),
-                3 => Ok(Response::EventIndexMark(
-                    parse_event_id(&lines)?,
-                    lines[2].to_owned(),
-                )),
-                _ => Err(ClientError::TooManyLines),
-            },
-            EVENT_BEGIN => Ok(Response::EventBegin(parse_event_id(&lines)?)),
-            EVENT_END => Ok(Response::EventEnd(parse_event_id(&lines)?)),
-            EVENT_CANCELED => Ok(Response::EventCanceled(parse_event_id(&lines)?)),
-            EVENT_PAUSED => Ok(Response::EventPaused(parse_event_id(&lines)?)),
-            EVENT_RESUMED => Ok(Response::EventResumed(parse_event_id(&lines)?)),
-            _ => panic!("error should have been caught earlier"),
         }
     }
 
     /// Check status of answer, discard lines.
     pub fn check_status(&mut self, expected_code: ReturnCode) -> ClientResult<&mut Self> {
-        crate::protocol::receive_answer(&mut self.input, None).and_then(|status| {
+        let mut lines = Vec::new();
+        self.receive_answer(&mut lines).and_then(|status| {
             if status.code == expected_code {
                 Ok(self)
             } else {
@@ -651,52 +2211,99 @@ impl<S: Read + Write + Source> Client<S> {
     pub fn receive_message_id(&mut self) -> ClientResult<MessageId> {
         let mut lines = Vec::new();
         match self.receive_answer(&mut lines)?.code {
-            OK_MESSAGE_QUEUED | OK_LAST_MSG => Ok(parse_single_integer(&lines)?),
+            OK_MESSAGE_QUEUED => {
+                let id = parse_single_integer(&lines)?;
+                self.messages.queued(id);
+                Ok(id)
+            }
+            OK_LAST_MSG => Ok(parse_single_integer(&lines)?),
             _ => Err(ClientError::invalid_data("not a message id")),
         }
     }
 
+    /// State of a message queued by this client.
+    ///
+    /// SSIP has no command to query it, so the state is derived from the message id and
+    /// the notifications received so far. It is only accurate if notifications for begin,
+    /// end and cancel are enabled. Messages that are not known return [`MessageState::Unknown`].
+    pub fn message_status(&self, id: &MessageId) -> MessageState {
+        self.messages.state(*id)
+    }
+
+    /// Return true if a message of this client has begun and is not finished.
+    ///
+    /// SSIP has no command for it, so it's derived from the begin, end and cancel events.
+    /// It's only accurate if these notifications are enabled and this client receives the
+    /// events itself, not the reader of a split client for instance. A paused message is
+    /// still considered as spoken.
+    pub fn is_speaking(&self) -> bool {
+        self.messages.is_speaking()
+    }
+
+    /// Identifier of the last message queued by this client, if any.
+    pub fn last_message_id(&self) -> Option<MessageId> {
+        self.messages.last
+    }
+
     /// Receive client id
+    ///
+    /// Accept both the answer to `HISTORY GET CLIENT_ID` and a `CLIENT_NAME` reply carrying the id.
     pub fn receive_client_id(&mut self) -> ClientResult<ClientId> {
-        self.receive_string(OK_CLIENT_ID_SENT).and_then(|s| {
-            s.parse()
-                .map_err(|_| ClientError::invalid_data("invalid client id"))
-        })
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines)?;
+        parse_client_id(&status, &lines)
     }
 
     /// Receive a list of synthesis voices
+    ///
+    /// The whole list is rejected if one line is invalid, see
+    /// [`Client::receive_synthesis_voices_lenient`] to keep the valid voices.
     pub fn receive_synthesis_voices(&mut self) -> ClientResult<Vec<SynthesisVoice>> {
         self.receive_lines(OK_VOICES_LIST_SENT)
             .and_then(|lines| parse_typed_lines::<SynthesisVoice>(&lines))
     }
 
+    /// Receive a list of synthesis voices, skipping the invalid lines.
+    ///
+    /// The lines that can't be parsed are returned apart with their error, so that a voice
+    /// picker stays usable when an output module sends an odd line. Errors of the answer
+    /// itself, such as an unexpected status, are still returned as an error.
+    pub fn receive_synthesis_voices_lenient(
+        &mut self,
+    ) -> ClientResult<(Vec<SynthesisVoice>, InvalidLines)> {
+        self.receive_lines(OK_VOICES_LIST_SENT)
+            .map(|lines| parse_typed_lines_lenient::<SynthesisVoice>(&lines))
+    }
+
     /// Receive a notification
     pub fn receive_event(&mut self) -> ClientResult<Event> {
-        let mut lines = Vec::new();
-        crate::protocol::receive_answer(&mut self.input, Some(&mut lines)).and_then(|status| {
-            if lines.len() < 2 {
-                Err(ClientError::unexpected_eof("event truncated"))
-            } else {
-                let message = &lines[0];
-                let client = &lines[1];
-                match status.code {
-                    700 => {
-                        if lines.len() != 3 {
-                            Err(ClientError::unexpected_eof("index markevent truncated"))
-                        } else {
-                            let mark = lines[3].to_owned();
-                            Ok(Event::index_mark(mark, message, client))
-                        }
-                    }
-                    701 => Ok(Event::begin(message, client)),
-                    702 => Ok(Event::end(message, client)),
-                    703 => Ok(Event::cancel(message, client)),
-                    704 => Ok(Event::pause(message, client)),
-                    705 => Ok(Event::resume(message, client)),
-                    _ => Err(ClientError::invalid_data("wrong status code for event")),
-                }
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+        self.writer.output.flush()?;
+        self.discard_pending_answers()?;
+        let event = read_event(&mut self.input, &mut self.partial)?;
+        self.messages.update(&event.id.message, &event.ntype);
+        Ok(event)
+    }
+
+    /// Read events and pass them to `handler` until it breaks or the connection is closed.
+    ///
+    /// Events queued while waiting for answers are handled first. A connection closed by
+    /// the server ends the loop without error.
+    pub fn run_event_loop(
+        &mut self,
+        mut handler: impl FnMut(Event) -> ControlFlow<()>,
+    ) -> ClientResult<()> {
+        loop {
+            let event = match self.receive_event() {
+                Err(ClientError::Disconnected) => return Ok(()),
+                result => result?,
+            };
+            if handler(event).is_break() {
+                return Ok(());
             }
-        })
+        }
     }
 
     /// Receive a list of client status from history.
@@ -705,9 +2312,18 @@ impl<S: Read + Write + Source> Client<S> {
             .and_then(|lines| parse_typed_lines::<HistoryClientStatus>(&lines))
     }
 
-    /// Check the result of `set_client_name`.
+    /// Check the result of `set_client_name` and store the client id if the server sent it.
     pub fn check_client_name_set(&mut self) -> ClientResult<&mut Self> {
-        self.check_status(OK_CLIENT_NAME_SET)
+        let mut lines = Vec::new();
+        let status = self.receive_answer(&mut lines)?;
+        if status.code == OK_CLIENT_NAME_SET {
+            let handshake = parse_handshake(&status, &lines);
+            self.client_id = handshake.client_id;
+            self.handshake = Some(handshake);
+            Ok(self)
+        } else {
+            Err(ClientError::UnexpectedStatus(status.code))
+        }
     }
 
     /// Check if server accept data.
@@ -722,11 +2338,506 @@ impl<S: Read + Write + Source> Client<S> {
         poll: &mio::Poll,
         input_token: mio::Token,
         output_token: mio::Token,
+    ) -> io::Result<()> {
+        self.register_with(poll.registry(), input_token, output_token)
+    }
+
+    /// Register the socket in a registry, that may be a clone used in another thread.
+    #[cfg(feature = "async-mio")]
+    pub fn register_with(
+        &mut self,
+        registry: &mio::Registry,
+        input_token: mio::Token,
+        output_token: mio::Token,
+    ) -> io::Result<()> {
+        registry.register(self.input.get_mut(), input_token, mio::Interest::READABLE)?;
+        registry.register(
+            self.writer.output.get_mut(),
+            output_token,
+            mio::Interest::WRITABLE,
+        )?;
+        Ok(())
+    }
+
+    /// Register the socket once with a single token and the given interest.
+    ///
+    /// A client that only listens to notifications can register with
+    /// `mio::Interest::READABLE` and doesn't get writable events. With both interests, the
+    /// token is used for readable and writable events. Use [`Client::deregister`] to
+    /// deregister it.
+    #[cfg(feature = "async-mio")]
+    pub fn register_interest(
+        &mut self,
+        poll: &mio::Poll,
+        token: mio::Token,
+        interest: mio::Interest,
     ) -> io::Result<()> {
         poll.registry()
-            .register(self.input.get_mut(), input_token, mio::Interest::READABLE)?;
+            .register(self.input.get_mut(), token, interest)
+    }
+
+    /// Register again the socket already registered, for example to change the tokens.
+    ///
+    /// A new connection must be registered with [`Client::register`] instead: deregister
+    /// the old client, connect the new one and register it.
+    #[cfg(feature = "async-mio")]
+    pub fn reregister(
+        &mut self,
+        poll: &mio::Poll,
+        input_token: mio::Token,
+        output_token: mio::Token,
+    ) -> io::Result<()> {
         poll.registry()
-            .register(self.output.get_mut(), output_token, mio::Interest::WRITABLE)?;
+            .reregister(self.input.get_mut(), input_token, mio::Interest::READABLE)?;
+        poll.registry().reregister(
+            self.writer.output.get_mut(),
+            output_token,
+            mio::Interest::WRITABLE,
+        )?;
+        Ok(())
+    }
+
+    /// Deregister the socket, so that a long-lived poll stops reporting its events.
+    #[cfg(feature = "async-mio")]
+    pub fn deregister(&mut self, poll: &mio::Poll) -> io::Result<()> {
+        poll.registry().deregister(self.input.get_mut())?;
+        match poll.registry().deregister(self.writer.output.get_mut()) {
+            // Not registered by `register_interest`.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(all(unix, not(feature = "async-mio")))]
+impl<S: Read + Write + Source> Client<S> {
+    /// Return true if data can be read without blocking.
+    ///
+    /// Data already buffered counts as readable, otherwise the socket is polled with a zero
+    /// timeout. It's a hint subject to races, a closed connection is also readable.
+    pub fn is_readable(&self) -> io::Result<bool> {
+        self.wait_readable(Duration::ZERO)
+    }
+
+    /// Wait at most `timeout` until data can be read.
+    fn wait_readable(&self, timeout: Duration) -> io::Result<bool> {
+        if !self.input.buffer().is_empty() {
+            return Ok(true);
+        }
+        let mut fds = [libc::pollfd {
+            fd: self.input.get_ref().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        let timeout = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        // SAFETY: the array is valid for the duration of the call and its length is passed.
+        match unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) } {
+            -1 => Err(io::Error::last_os_error()),
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    /// Receive an event if one arrives within `timeout`.
+    ///
+    /// Answers to requests sent with [`Client::send_no_wait`] are discarded on the way.
+    fn poll_event(&mut self, timeout: Duration) -> ClientResult<Option<Event>> {
+        if self.events.is_empty() && !self.wait_readable(timeout)? {
+            return Ok(None);
+        }
+        self.discard_pending_answers()?;
+        if self.events.is_empty() && !self.is_readable()? {
+            return Ok(None);
+        }
+        self.receive_event().map(Some)
+    }
+
+    /// Same as [`Client::run_event_loop`], but sends the requests received from `commands`
+    /// between events.
+    ///
+    /// The queue is checked at least every `interval`. Requests are sent with
+    /// [`Client::send_no_wait`], so they must be answered by a single status line and the
+    /// errors returned by the server are lost. Once all the senders are dropped, only
+    /// events are read.
+    pub fn run_event_loop_with_commands(
+        &mut self,
+        commands: &mpsc::Receiver<Request>,
+        interval: Duration,
+        mut handler: impl FnMut(Event) -> ControlFlow<()>,
+    ) -> ClientResult<()> {
+        loop {
+            loop {
+                match commands.try_recv() {
+                    Ok(request) => {
+                        self.send_no_wait(request)?;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => return self.run_event_loop(handler),
+                }
+            }
+            let event = match self.poll_event(interval) {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                Err(ClientError::Disconnected) => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            if handler(event).is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "async-mio"))]
+impl<S: Read + Write + Source + ReadTimeout> Client<S> {
+    /// Speak one line of text and wait at most `timeout` until it is spoken or canceled.
+    ///
+    /// Same as [`Client::say_line_blocking`] but fails with [`ClientError::Timeout`] if the
+    /// end of the message isn't notified in time. In that case, the message is still tracked:
+    /// pass [`Client::last_message_id`] to [`Client::message_status`] to know if it began.
+    /// The read timeout of the stream is restored before returning.
+    pub fn say_line_blocking_timeout(&mut self, text: &str, timeout: Duration) -> ClientResult<()> {
+        self.enable_notifications(&[
+            NotificationType::Begin,
+            NotificationType::End,
+            NotificationType::Cancel,
+        ])?;
+        let id = self.say_line(text)?;
+        let deadline = Instant::now() + timeout;
+        let previous = self.input.get_ref().read_timeout()?;
+        let result = self.wait_message_end_until(id, deadline);
+        self.input.get_ref().set_read_timeout(previous)?;
+        result
+    }
+
+    /// Read events until the message is spoken or canceled or the deadline is reached.
+    fn wait_message_end_until(&mut self, id: MessageId, deadline: Instant) -> ClientResult<()> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ClientError::Timeout);
+            }
+            self.input.get_ref().set_read_timeout(Some(remaining))?;
+            let event = match self.receive_event() {
+                // Depending on the platform, an expired read timeout is reported as `WouldBlock`.
+                Err(ClientError::NotReady) | Err(ClientError::Timeout) => {
+                    return Err(ClientError::Timeout)
+                }
+                Err(ClientError::Io(err))
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Err(ClientError::Timeout)
+                }
+                result => result?,
+            };
+            if event.matches(&id) && matches!(event.ntype, EventType::End | EventType::Cancel) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Core commands of the synchronous client.
+///
+/// It's the synchronous counterpart of `AsyncSsipClient`: take a client bounded by this
+/// trait to run the same code on a [`Client`] and, with the `mock` feature, on a
+/// `MockClient` without a server. Unlike the methods of [`Client`], the commands check the
+/// status of the answer.
+///
+/// The methods have the same names as those of [`Client`], so they must be called through
+/// the trait on a concrete client, for example `SsipClient::quit(&mut client)`.
+///
+/// ```no_run
+/// use ssip_client_async::{client::SsipClient, ClientResult, ClientScope, MessageId, Rate};
+///
+/// fn say_slowly<C: SsipClient>(client: &mut C, text: &str) -> ClientResult<MessageId> {
+///     client.set_rate(ClientScope::Current, Rate::try_from(-50).unwrap())?;
+///     client.say_line(text)
+/// }
+/// ```
+pub trait SsipClient {
+    /// Set the client name and check the answer.
+    fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<()>;
+
+    /// Speak a text and return the id of the queued message.
+    ///
+    /// Lines starting with a dot are escaped.
+    fn say_line(&mut self, text: &str) -> ClientResult<MessageId>;
+
+    /// Set the rate of speech.
+    fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<()>;
+
+    /// Set the pitch of the voice.
+    fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<()>;
+
+    /// Set the volume of the voice.
+    fn set_volume(&mut self, scope: ClientScope, value: Volume) -> ClientResult<()>;
+
+    /// Set the priority of the next messages.
+    fn set_priority(&mut self, priority: Priority) -> ClientResult<()>;
+
+    /// Stop the messages.
+    fn stop(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Cancel the messages.
+    fn cancel(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Pause the messages.
+    fn pause(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Resume the messages.
+    fn resume(&mut self, scope: MessageScope) -> ClientResult<()>;
+
+    /// Receive the next notification.
+    fn receive_event(&mut self) -> ClientResult<Event>;
+
+    /// Close the connection.
+    fn quit(&mut self) -> ClientResult<()>;
+}
+
+impl<S: Read + Write + Source> SsipClient for Client<S> {
+    fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<()> {
+        Client::set_client_name(self, client_name)?.check_client_name_set()?;
+        Ok(())
+    }
+
+    fn say_line(&mut self, text: &str) -> ClientResult<MessageId> {
+        Client::say_line(self, text)
+    }
+
+    fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<()> {
+        Client::set_rate(self, scope, value)?.check_status(OK_RATE_SET)?;
+        Ok(())
+    }
+
+    fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<()> {
+        Client::set_pitch(self, scope, value)?.check_status(OK_PITCH_SET)?;
+        Ok(())
+    }
+
+    fn set_volume(&mut self, scope: ClientScope, value: Volume) -> ClientResult<()> {
+        Client::set_volume(self, scope, value)?.check_status(OK_VOLUME_SET)?;
+        Ok(())
+    }
+
+    fn set_priority(&mut self, priority: Priority) -> ClientResult<()> {
+        Client::set_priority(self, priority)?.check_status(OK_PRIORITY_SET)?;
+        Ok(())
+    }
+
+    fn stop(&mut self, scope: MessageScope) -> ClientResult<()> {
+        Client::stop(self, scope)?.check_status(OK_STOPPED)?;
+        Ok(())
+    }
+
+    fn cancel(&mut self, scope: MessageScope) -> ClientResult<()> {
+        Client::cancel(self, scope)?.check_status(OK_CANCELED)?;
         Ok(())
     }
+
+    fn pause(&mut self, scope: MessageScope) -> ClientResult<()> {
+        Client::pause(self, scope)?.check_status(OK_PAUSED)?;
+        Ok(())
+    }
+
+    fn resume(&mut self, scope: MessageScope) -> ClientResult<()> {
+        Client::resume(self, scope)?.check_status(OK_RESUMED)?;
+        Ok(())
+    }
+
+    fn receive_event(&mut self) -> ClientResult<Event> {
+        Client::receive_event(self)
+    }
+
+    fn quit(&mut self) -> ClientResult<()> {
+        Client::quit(self)?.check_status(OK_BYE)?;
+        Ok(())
+    }
+}
+
+/// Requests sent in one write, the responses are read afterwards.
+///
+/// With N requests, it costs one round trip instead of N. Each request must have exactly one
+/// response, so `SPEAK` and the text count as two requests. Events received meanwhile are
+/// queued in the client, see [`Client::drain_events`].
+///
+/// ```no_run
+/// use ssip_client_async::{fifo, ClientScope, Pitch, Rate, Request};
+/// let mut client = fifo::Builder::new().build()?;
+/// let responses = client
+///     .pipeline()
+///     .push(Request::SetRate(ClientScope::Current, Rate::default()))
+///     .push(Request::SetPitch(ClientScope::Current, Pitch::default()))
+///     .execute()?;
+/// # Ok::<(), ssip_client_async::ClientError>(())
+/// ```
+pub struct Pipeline<'a, S: Read + Write + Source> {
+    client: &'a mut Client<S>,
+    requests: Vec<Request>,
+}
+
+impl<S: Read + Write + Source> Pipeline<'_, S> {
+    /// Add a request to the pipeline.
+    pub fn push(&mut self, request: Request) -> &mut Self {
+        self.requests.push(request);
+        self
+    }
+
+    /// Send all the requests and return the responses in the same order.
+    ///
+    /// If the server rejects a request, the remaining responses are still read and the first
+    /// failure is returned as [`ClientError::PipelineFailed`].
+    pub fn execute(&mut self) -> ClientResult<Vec<Response>> {
+        let requests = std::mem::take(&mut self.requests);
+        let count = requests.len();
+        for (written, request) in requests.into_iter().enumerate() {
+            if let Err(err) = self.client.write(request) {
+                // The replies to the requests already written are still expected.
+                for _ in 0..written {
+                    match self.client.receive_reply() {
+                        Ok(_) | Err(ClientError::Ssip(_)) => (),
+                        Err(_) => break,
+                    }
+                }
+                return Err(err);
+            }
+        }
+        let mut responses = Vec::with_capacity(count);
+        let mut first_error = None;
+        for index in 0..count {
+            match self.client.receive_reply() {
+                Ok(response) => responses.push(response),
+                Err(err @ ClientError::Ssip(_)) => {
+                    first_error.get_or_insert(ClientError::PipelineFailed {
+                        index,
+                        source: Box::new(err),
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(responses),
+        }
+    }
+}
+
+/// Reading half of a [`Client`], see [`Client::split`].
+///
+/// Events queued by the client before the split are returned first.
+pub struct ClientReader<S: Read + Write + Source> {
+    input: io::BufReader<S>,
+    partial: PartialAnswer,
+    events: VecDeque<Event>,
+}
+
+impl<S: Read + Write + Source> ClientReader<S> {
+    /// Receive one response or event.
+    pub fn receive(&mut self) -> ClientResult<Response> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event_response(event));
+        }
+        read_response(&mut self.input, &mut self.partial)
+    }
+
+    /// Receive a notification
+    pub fn receive_event(&mut self) -> ClientResult<Event> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+        read_event(&mut self.input, &mut self.partial)
+    }
+}
+
+/// Writing half of a [`Client`], see [`Client::split`].
+///
+/// Like the client, it sends `QUIT` when dropped unless it has already been sent.
+pub struct ClientWriter<S: Read + Write + Source> {
+    output: io::BufWriter<S>,
+    quit_on_drop: bool,
+}
+
+impl<S: Read + Write + Source> ClientWriter<S> {
+    /// Send a request
+    pub fn send(&mut self, request: Request) -> ClientResult<&mut Self> {
+        if request == Request::Quit {
+            self.quit_on_drop = false;
+        }
+        write_request(&mut self.output, request)?;
+        self.output.flush()?;
+        Ok(self)
+    }
+
+    /// Don't send `QUIT` when the writer is dropped.
+    pub fn forget_quit(&mut self) -> &mut Self {
+        self.quit_on_drop = false;
+        self
+    }
+}
+
+impl<S: Read + Write + Source> Drop for ClientWriter<S> {
+    fn drop(&mut self) {
+        if self.quit_on_drop {
+            // Best effort, the error can't be propagated.
+            let _ = self.send(Request::Quit);
+        }
+    }
+}
+
+/// Client whose events are read in a background thread, see [`Client::subscribe_events`].
+///
+/// It sends `QUIT` when dropped unless it has already been sent, and waits for the thread.
+/// If `QUIT` is never sent, because [`Client::forget_quit`] was called before subscribing,
+/// the thread is detached instead and keeps reading until the connection is closed.
+/// Answers are received as [`Response`] values, errors returned by the server as [`ClientError::Ssip`].
+///
+/// The client is `Send` when the stream is, but not `Sync` because answers are read from
+/// a channel receiver.
+pub struct EventClient<S: Read + Write + Source> {
+    writer: ClientWriter<S>,
+    responses: mpsc::Receiver<ClientResult<Response>>,
+    thread: Option<thread::JoinHandle<()>>,
+    quit_sent: bool,
+}
+
+impl<S: Read + Write + Source> EventClient<S> {
+    /// Send a request
+    pub fn send(&mut self, request: Request) -> ClientResult<&mut Self> {
+        let quit = request == Request::Quit;
+        self.writer.send(request)?;
+        self.quit_sent |= quit;
+        Ok(self)
+    }
+
+    /// Receive the next response that is not an event.
+    pub fn receive(&mut self) -> ClientResult<Response> {
+        self.responses
+            .recv()
+            .unwrap_or(Err(ClientError::Disconnected))
+    }
+
+    /// Close the connection, the events thread stops once the server has closed it.
+    pub fn quit(&mut self) -> ClientResult<&mut Self> {
+        self.send(Request::Quit)
+    }
+}
+
+impl<S: Read + Write + Source> Drop for EventClient<S> {
+    fn drop(&mut self) {
+        if self.writer.quit_on_drop {
+            // Best effort, the error can't be propagated.
+            let _ = self.quit();
+        }
+        if let Some(thread) = self.thread.take() {
+            // The server closes the connection after QUIT, which ends the thread. Otherwise
+            // nothing ends it and joining would block.
+            if self.quit_sent {
+                let _ = thread.join();
+            }
+        }
+    }
 }
@@ -267,6 +267,11 @@ pub const ERR_PITCH_RANGE_TOO_HIGH: ReturnCode = 415;
 /// Client error: ERR PITCH RANGE TOO LOW
 pub const ERR_PITCH_RANGE_TOO_LOW: ReturnCode = 416;
 
+/// Client error: ERR QUEUE TOO BIG
+///
+/// Returned when the message queue of the server is full.
+pub const ERR_QUEUE_FULL: ReturnCode = 431;
+
 /// Client error: ERR INVALID COMMAND
 pub const ERR_INVALID_COMMAND: ReturnCode = 500;
 
@@ -7,11 +7,54 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use log::debug;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use crate::net::{parse_address, Address};
+
 const SPEECHD_APPLICATION_NAME: &str = "speech-dispatcher";
 const SPEECHD_SOCKET_NAME: &str = "speechd.sock";
+const SPEECHD_ADDRESS_VAR: &str = "SPEECHD_ADDRESS";
+
+/// Return the socket path in the same order as Speech Dispatcher.
+///
+/// The path is taken from `SPEECHD_ADDRESS` if it is a `unix_socket:` address. Otherwise it
+/// is the standard socket in the [freedesktop.org](https://www.freedesktop.org/) runtime
+/// directory `$XDG_RUNTIME_DIR` or, if not set, `/run/user/<uid>`.
+pub fn default_socket_path() -> io::Result<PathBuf> {
+    let path = resolve_socket_path(
+        std::env::var(SPEECHD_ADDRESS_VAR).ok().as_deref(),
+        dirs::runtime_dir(),
+        Some(current_uid()),
+    )?;
+    debug!("speech-dispatcher socket: {}", path.display());
+    Ok(path)
+}
+
+fn resolve_socket_path(
+    address: Option<&str>,
+    runtime_dir: Option<PathBuf>,
+    uid: Option<u32>,
+) -> io::Result<PathBuf> {
+    if let Some(Ok(Address::Unix(path))) = address.map(parse_address) {
+        return Ok(path);
+    }
+    runtime_dir
+        .or_else(|| uid.map(|uid| PathBuf::from(format!("/run/user/{}", uid))))
+        .map(|runtime_dir| {
+            runtime_dir
+                .join(SPEECHD_APPLICATION_NAME)
+                .join(SPEECHD_SOCKET_NAME)
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unix socket not found"))
+}
+
+/// Real user id of the current process.
+fn current_uid() -> u32 {
+    // SAFETY: getuid has no precondition and always succeeds.
+    unsafe { libc::getuid() }
+}
 
 struct FifoPath {
     path: Option<PathBuf>,
@@ -29,17 +72,8 @@ impl FifoPath {
         self.path = Some(path.as_ref().to_path_buf());
     }
 
-    /// Return the standard socket according to the [freedesktop.org](https://www.freedesktop.org/) specification.
     fn default_path() -> io::Result<PathBuf> {
-        match dirs::runtime_dir() {
-            Some(runtime_dir) => Ok(runtime_dir
-                .join(SPEECHD_APPLICATION_NAME)
-                .join(SPEECHD_SOCKET_NAME)),
-            None => Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "unix socket not found",
-            )),
-        }
+        default_socket_path()
     }
 
     fn get(&self) -> io::Result<PathBuf> {
@@ -53,19 +87,82 @@ impl FifoPath {
 #[cfg(not(feature = "async-mio"))]
 mod synchronous {
     use std::io::{self, BufReader, BufWriter};
+    use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
     pub use std::os::unix::net::UnixStream;
     use std::path::Path;
     use std::process::Command;
-    use std::time::Duration;
+    use std::thread;
+    use std::time::{Duration, Instant};
 
     use crate::client::Client;
     use crate::net::StreamMode;
+    use crate::speech::{EscapePolicy, TextNormalizer};
+    use crate::types::{ClientName, ClientResult};
 
     use super::FifoPath;
 
+    impl Client<UnixStream> {
+        /// Create a client on a connected socket inherited from the parent process.
+        ///
+        /// The descriptor must be a connected unix stream socket, otherwise an error of kind
+        /// `InvalidInput` or `NotConnected` is returned. The client name is sent and the
+        /// answer checked before returning the client. The descriptor is closed when the
+        /// client is dropped or if an error occurs.
+        ///
+        /// # Safety
+        ///
+        /// The descriptor must be open and not owned by anything else, since the client
+        /// takes ownership of it.
+        pub unsafe fn from_raw_fd(fd: RawFd, client_name: &ClientName) -> ClientResult<Self> {
+            let fd = OwnedFd::from_raw_fd(fd);
+            check_stream_socket(&fd)?;
+            let input = UnixStream::from(fd);
+            // Fails if the socket is not connected or is not a unix socket.
+            input.peer_addr()?;
+            let output = input.try_clone()?;
+            let mut client = Client::new(BufReader::new(input), BufWriter::new(output));
+            client
+                .set_client_name(client_name.clone())?
+                .check_client_name_set()?;
+            Ok(client)
+        }
+    }
+
+    /// Check that the descriptor is a stream socket.
+    fn check_stream_socket(fd: &OwnedFd) -> io::Result<()> {
+        let mut socket_type: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let res = unsafe {
+            libc::getsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_TYPE,
+                &mut socket_type as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            return Err(match err.raw_os_error() {
+                Some(libc::ENOTSOCK) => io::Error::new(io::ErrorKind::InvalidInput, err),
+                _ => err,
+            });
+        }
+        if socket_type != libc::SOCK_STREAM {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a stream socket",
+            ));
+        }
+        Ok(())
+    }
+
     pub struct Builder {
         path: FifoPath,
         mode: StreamMode,
+        escape_policy: Option<EscapePolicy>,
+        chunking: bool,
+        normalizer: TextNormalizer,
     }
 
     impl Builder {
@@ -73,6 +170,9 @@ mod synchronous {
             Self {
                 path: FifoPath::new(),
                 mode: StreamMode::Blocking,
+                escape_policy: None,
+                chunking: false,
+                normalizer: TextNormalizer::default(),
             }
         }
 
@@ -94,6 +194,24 @@ mod synchronous {
             self
         }
 
+        /// Escape policy of the client, see [`Client::set_escape_policy`].
+        pub fn escape_policy(&mut self, policy: EscapePolicy) -> &mut Self {
+            self.escape_policy = Some(policy);
+            self
+        }
+
+        /// Split long texts in several messages, see [`Client::set_chunking`].
+        pub fn chunking(&mut self, chunking: bool) -> &mut Self {
+            self.chunking = chunking;
+            self
+        }
+
+        /// Transform the texts before sending them, see [`Client::set_normalizer`].
+        pub fn normalizer(&mut self, normalizer: TextNormalizer) -> &mut Self {
+            self.normalizer = normalizer;
+            self
+        }
+
         /// Spawn the speech-dispatcher daemon before creating the client
         pub fn with_spawn(&self) -> io::Result<&Self> {
             Command::new("speech-dispatcher")
@@ -112,7 +230,38 @@ mod synchronous {
             }
 
             let output = input.try_clone()?;
-            Ok(Client::new(BufReader::new(input), BufWriter::new(output)))
+            let mut client = Client::new(BufReader::new(input), BufWriter::new(output));
+            client
+                .set_escape_policy(self.escape_policy)
+                .set_chunking(self.chunking)
+                .set_normalizer(self.normalizer);
+            Ok(client)
+        }
+
+        /// Build the client, waiting for the socket if the server is not started yet.
+        ///
+        /// Connecting is retried every `interval` while the socket doesn't exist or refuses
+        /// the connection, until `deadline` has elapsed. Other errors, such as a permission
+        /// denied, are returned immediately.
+        pub fn build_with_retry(
+            &self,
+            deadline: Duration,
+            interval: Duration,
+        ) -> io::Result<Client<UnixStream>> {
+            let deadline = Instant::now() + deadline;
+            loop {
+                match self.build() {
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                        ) && Instant::now() + interval < deadline =>
+                    {
+                        thread::sleep(interval)
+                    }
+                    result => return result,
+                }
+            }
         }
     }
 }
@@ -210,6 +359,49 @@ pub mod asynchronous_tokio {
     }
 }
 
+#[cfg(feature = "async-io")]
+pub mod asynchronous_async_io {
+    use async_io::Async;
+    use futures_util::io::{BufReader, BufWriter};
+    use std::io;
+    pub use std::os::unix::net::UnixStream;
+    use std::path::Path;
+
+    use crate::async_io::{AsyncClient, AsyncIoClient};
+
+    use super::FifoPath;
+
+    pub struct Builder {
+        path: FifoPath,
+    }
+    impl Default for Builder {
+        fn default() -> Self {
+            Self {
+                path: FifoPath::new(),
+            }
+        }
+    }
+
+    impl Builder {
+        pub fn path<P>(&mut self, socket_path: P) -> &mut Self
+        where
+            P: AsRef<Path>,
+        {
+            self.path.set(socket_path);
+            self
+        }
+
+        pub async fn build(&self) -> io::Result<AsyncIoClient> {
+            let input = Async::<UnixStream>::connect(self.path.get()?).await?;
+            let output = Async::new(input.get_ref().try_clone()?)?;
+            Ok(AsyncClient::new(
+                BufReader::new(input),
+                BufWriter::new(output),
+            ))
+        }
+    }
+}
+
 #[cfg(feature = "async-mio")]
 pub use asynchronous::{Builder, UnixStream};
 
@@ -221,6 +413,9 @@ impl Default for Builder {
 
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+
+    use super::resolve_socket_path;
 
     #[test]
     fn test_fifo_path() -> std::io::Result<()> {
@@ -234,4 +429,36 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn socket_path_resolution() {
+        let runtime_dir = Some(PathBuf::from("/tmp/runtime"));
+        assert_eq!(
+            PathBuf::from("/tmp/speechd.sock"),
+            resolve_socket_path(
+                Some("unix_socket:/tmp/speechd.sock"),
+                runtime_dir.clone(),
+                Some(1000)
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("/tmp/runtime/speech-dispatcher/speechd.sock"),
+            resolve_socket_path(
+                Some("inet_socket:localhost:6560"),
+                runtime_dir.clone(),
+                Some(1000)
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("/tmp/runtime/speech-dispatcher/speechd.sock"),
+            resolve_socket_path(None, runtime_dir, Some(1000)).unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("/run/user/1000/speech-dispatcher/speechd.sock"),
+            resolve_socket_path(None, None, Some(1000)).unwrap()
+        );
+        assert!(resolve_socket_path(None, None, None).is_err());
+    }
 }
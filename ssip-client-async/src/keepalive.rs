@@ -0,0 +1,132 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2021-2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::time::{Duration, Instant};
+
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+use crate::constants::EVENT_INDEX_MARK;
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+use crate::types::ClientStatus;
+use crate::types::{ClientError, ClientResult, StatusLine};
+
+/// Idle time after which a client should probe the connection.
+#[derive(Debug)]
+pub(crate) struct Keepalive {
+    interval: Option<Duration>,
+    last_activity: Instant,
+    pending_replies: usize,
+}
+
+impl Keepalive {
+    pub(crate) fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    /// Record that a request was sent.
+    pub(crate) fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Record that a request was sent and that its reply is awaited.
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+    pub(crate) fn request_sent(&mut self) {
+        self.touch();
+        self.pending_replies += 1;
+    }
+
+    /// Record an answer read from the server. Events are not replies to requests.
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+    pub(crate) fn answer_received(&mut self, answer: &ClientStatus) {
+        if let Ok(status) | Err(ClientError::Ssip(status)) = answer {
+            if status.code < EVENT_INDEX_MARK {
+                self.pending_replies = self.pending_replies.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Return true if the keepalive is enabled, the connection has been idle long enough and
+    /// no reply is awaited.
+    pub(crate) fn is_due(&self) -> bool {
+        self.next_probe()
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Time of the next probe, or `None` if the keepalive is disabled or a reply is awaited.
+    pub(crate) fn next_probe(&self) -> Option<Instant> {
+        match self.interval {
+            Some(interval) if self.pending_replies == 0 => Some(self.last_activity + interval),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self {
+            interval: None,
+            last_activity: Instant::now(),
+            pending_replies: 0,
+        }
+    }
+}
+
+/// Interpret the answer to the probe: errors returned by the server prove that it's alive,
+/// other failures mean that the connection is lost.
+pub(crate) fn probe_result(result: ClientResult<StatusLine>) -> ClientResult<()> {
+    match result {
+        Ok(_) | Err(ClientError::Ssip(_)) => Ok(()),
+        Err(ClientError::Io(_)) => Err(ClientError::Disconnected),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Keepalive;
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+    use crate::types::{ClientError, StatusLine};
+
+    #[test]
+    fn due_after_interval() {
+        let mut keepalive = Keepalive::default();
+        assert!(!keepalive.is_due());
+        keepalive.set_interval(Some(Duration::ZERO));
+        assert!(keepalive.is_due());
+        keepalive.set_interval(Some(Duration::from_secs(3600)));
+        keepalive.touch();
+        assert!(!keepalive.is_due());
+        assert!(keepalive.next_probe().is_some());
+        keepalive.set_interval(None);
+        assert_eq!(None, keepalive.next_probe());
+    }
+
+    #[test]
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "async-io"))]
+    fn not_due_with_pending_reply() {
+        let mut keepalive = Keepalive::default();
+        keepalive.set_interval(Some(Duration::ZERO));
+        keepalive.request_sent();
+        assert!(!keepalive.is_due());
+        assert_eq!(None, keepalive.next_probe());
+        let event = StatusLine {
+            code: 702,
+            message: "END".to_string(),
+        };
+        keepalive.answer_received(&Ok(event));
+        assert!(!keepalive.is_due());
+        let error = StatusLine {
+            code: 431,
+            message: "ERR QUEUE TOO BIG".to_string(),
+        };
+        keepalive.answer_received(&Err(ClientError::Ssip(error)));
+        assert!(keepalive.is_due());
+    }
+}
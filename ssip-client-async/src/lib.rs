@@ -29,6 +29,7 @@
 #[macro_use]
 mod protocol;
 
+mod keepalive;
 mod poll;
 pub use ssip as types;
 
@@ -37,16 +38,44 @@ pub mod constants;
 #[cfg(unix)]
 pub mod fifo;
 pub mod net;
+pub mod parse;
+pub mod retry;
+pub mod shared;
+pub mod speech;
 pub mod tcp;
+#[cfg(feature = "ws")]
+pub mod ws;
 
 #[cfg(any(not(feature = "async-mio"), doc))]
-pub use client::Client;
+pub use client::{Client, EventClient, Pipeline};
 
-#[cfg(any(feature = "async-std", doc))]
+#[cfg(any(
+    feature = "tokio",
+    feature = "async-std",
+    feature = "async-io",
+    feature = "mock"
+))]
+pub mod async_client;
+#[cfg(all(unix, feature = "async-io"))]
+pub mod async_io;
+#[cfg(any(feature = "async-std", feature = "async-io"))]
 pub mod async_std;
-#[cfg(any(feature = "tokio", doc))]
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "tokio")]
 pub mod tokio;
 
+#[cfg(any(
+    feature = "tokio",
+    feature = "async-std",
+    feature = "async-io",
+    feature = "mock"
+))]
+pub use async_client::AsyncSsipClient;
 pub use constants::*;
 pub use poll::QueuedClient;
+pub use protocol::encode_data_block;
+pub use retry::RetryPolicy;
+pub use shared::SharedClient;
+pub use speech::{Boundary, ControlChars, EscapePolicy, SpeechBuilder, TextNormalizer};
 pub use types::*;
@@ -0,0 +1,202 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Client without server, to test code written against [`AsyncSsipClient`] or
+//! [`SsipClient`].
+//!
+//! The commands are recorded as [`Request`] values instead of being sent. Results and
+//! events are scripted in advance.
+//!
+//! Only the commands of the traits are covered, not the methods specific to a client.
+//! Since both traits have the same methods, code using the mock must import only one of
+//! them or call the methods through the trait.
+//!
+//! ```
+//! use ssip_client_async::{mock::MockClient, AsyncSsipClient, MessageScope, Request};
+//!
+//! async fn on_stop_key<C: AsyncSsipClient>(client: &mut C) {
+//!     let _ = client.stop(MessageScope::All).await;
+//! }
+//!
+//! let mut client = MockClient::new();
+//! # smol::block_on(async {
+//! on_stop_key(&mut client).await;
+//! # });
+//! assert_eq!(&[Request::Stop(MessageScope::All)], client.requests());
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::async_client::AsyncSsipClient;
+use crate::client::SsipClient;
+use crate::types::*;
+
+/// Client recording the commands instead of sending them.
+///
+/// Each command takes the next scripted result, or succeeds when none is left. Message ids
+/// returned by `say_line` are numbered from 1.
+#[derive(Debug, Default)]
+pub struct MockClient {
+    requests: Vec<Request>,
+    results: VecDeque<ClientResult<()>>,
+    events: VecDeque<Event>,
+    last_message_id: MessageId,
+}
+
+impl MockClient {
+    /// Create a client with no scripted result or event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests recorded so far, including those of failed commands.
+    ///
+    /// `say_line` records [`Request::Speak`] followed by
+    /// [`Request::SendLine`] with the text as given.
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    /// Return the requests recorded so far and clear the log.
+    pub fn take_requests(&mut self) -> Vec<Request> {
+        std::mem::take(&mut self.requests)
+    }
+
+    /// Script the result of the next command without one.
+    pub fn push_result(&mut self, result: ClientResult<()>) -> &mut Self {
+        self.results.push_back(result);
+        self
+    }
+
+    /// Queue an event returned by `receive_event`.
+    ///
+    /// When no event is left, it fails with [`ClientError::Disconnected`].
+    pub fn push_event(&mut self, event: Event) -> &mut Self {
+        self.events.push_back(event);
+        self
+    }
+
+    /// Record the requests of a command and return its scripted result.
+    fn command(&mut self, requests: impl IntoIterator<Item = Request>) -> ClientResult<()> {
+        self.requests.extend(requests);
+        self.results.pop_front().unwrap_or(Ok(()))
+    }
+
+    /// Record a text to speak and return the id of the new message.
+    fn speak_text(&mut self, text: &str) -> ClientResult<MessageId> {
+        self.command([Request::Speak, Request::SendLine(text.to_string())])?;
+        self.last_message_id += 1;
+        Ok(self.last_message_id)
+    }
+
+    /// Return the next scripted event.
+    fn next_event(&mut self) -> ClientResult<Event> {
+        self.events.pop_front().ok_or(ClientError::Disconnected)
+    }
+}
+
+impl SsipClient for MockClient {
+    fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<()> {
+        self.command([Request::SetName(client_name)])
+    }
+
+    fn say_line(&mut self, text: &str) -> ClientResult<MessageId> {
+        self.speak_text(text)
+    }
+
+    fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<()> {
+        self.command([Request::SetRate(scope, value)])
+    }
+
+    fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<()> {
+        self.command([Request::SetPitch(scope, value)])
+    }
+
+    fn set_volume(&mut self, scope: ClientScope, value: Volume) -> ClientResult<()> {
+        self.command([Request::SetVolume(scope, value)])
+    }
+
+    fn set_priority(&mut self, priority: Priority) -> ClientResult<()> {
+        self.command([Request::SetPriority(priority)])
+    }
+
+    fn stop(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Stop(scope)])
+    }
+
+    fn cancel(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Cancel(scope)])
+    }
+
+    fn pause(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Pause(scope)])
+    }
+
+    fn resume(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Resume(scope)])
+    }
+
+    fn receive_event(&mut self) -> ClientResult<Event> {
+        self.next_event()
+    }
+
+    fn quit(&mut self) -> ClientResult<()> {
+        self.command([Request::Quit])
+    }
+}
+
+impl AsyncSsipClient for MockClient {
+    async fn set_client_name(&mut self, client_name: ClientName) -> ClientResult<()> {
+        self.command([Request::SetName(client_name)])
+    }
+
+    async fn say_line(&mut self, text: &str) -> ClientResult<MessageId> {
+        self.speak_text(text)
+    }
+
+    async fn set_rate(&mut self, scope: ClientScope, value: Rate) -> ClientResult<()> {
+        self.command([Request::SetRate(scope, value)])
+    }
+
+    async fn set_pitch(&mut self, scope: ClientScope, value: Pitch) -> ClientResult<()> {
+        self.command([Request::SetPitch(scope, value)])
+    }
+
+    async fn set_volume(&mut self, scope: ClientScope, value: Volume) -> ClientResult<()> {
+        self.command([Request::SetVolume(scope, value)])
+    }
+
+    async fn set_priority(&mut self, priority: Priority) -> ClientResult<()> {
+        self.command([Request::SetPriority(priority)])
+    }
+
+    async fn stop(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Stop(scope)])
+    }
+
+    async fn cancel(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Cancel(scope)])
+    }
+
+    async fn pause(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Pause(scope)])
+    }
+
+    async fn resume(&mut self, scope: MessageScope) -> ClientResult<()> {
+        self.command([Request::Resume(scope)])
+    }
+
+    async fn receive_event(&mut self) -> ClientResult<Event> {
+        self.next_event()
+    }
+
+    async fn quit(&mut self) -> ClientResult<()> {
+        self.command([Request::Quit])
+    }
+}
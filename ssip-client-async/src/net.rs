@@ -7,6 +7,52 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+use std::io;
+use std::path::PathBuf;
+#[cfg(not(feature = "async-mio"))]
+use std::time::Duration;
+
+use crate::types::{ClientError, ClientResult};
+
+/// Default port of the server on inet sockets.
+pub const SPEECHD_DEFAULT_PORT: u16 = 6560;
+
+/// Server address as in the `SPEECHD_ADDRESS` environment variable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// `unix_socket:/path/to/socket`
+    Unix(PathBuf),
+    /// `inet_socket:host:port`, the port is optional.
+    Inet(String, u16),
+}
+
+/// Parse an address `unix_socket:<path>` or `inet_socket:<host>[:<port>]`.
+pub fn parse_address(s: &str) -> ClientResult<Address> {
+    let (method, target) = s
+        .split_once(':')
+        .ok_or_else(|| invalid_input!("missing socket type in address {}", s))?;
+    match method {
+        "unix_socket" if !target.is_empty() => Ok(Address::Unix(PathBuf::from(target))),
+        "inet_socket" => {
+            let (host, port) = match target.split_once(':') {
+                Some((host, port)) => (
+                    host,
+                    port.parse::<u16>()
+                        .map_err(|_| invalid_input!("invalid port in address {}", s))?,
+                ),
+                None => (target, SPEECHD_DEFAULT_PORT),
+            };
+            if host.is_empty() {
+                Err(invalid_input!("missing host in address {}", s))
+            } else {
+                Ok(Address::Inet(host.to_string(), port))
+            }
+        }
+        "unix_socket" => Err(invalid_input!("missing path in address {}", s)),
+        _ => Err(invalid_input!("unknown socket type in address {}", s)),
+    }
+}
+
 #[cfg(not(feature = "async-mio"))]
 pub(crate) enum StreamMode {
     Blocking,
@@ -14,5 +60,73 @@ pub(crate) enum StreamMode {
     TimeOut(std::time::Duration),
 }
 
+/// Stream whose read timeout can be changed after the client is built.
+#[cfg(not(feature = "async-mio"))]
+pub trait ReadTimeout {
+    fn read_timeout(&self) -> io::Result<Option<Duration>>;
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+macro_rules! impl_read_timeout {
+    ($stream:ty) => {
+        #[cfg(not(feature = "async-mio"))]
+        impl ReadTimeout for $stream {
+            fn read_timeout(&self) -> io::Result<Option<Duration>> {
+                <$stream>::read_timeout(self)
+            }
+
+            fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+                <$stream>::set_read_timeout(self, timeout)
+            }
+        }
+    };
+}
+
+impl_read_timeout!(std::os::unix::net::UnixStream);
+impl_read_timeout!(std::net::TcpStream);
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use std::io;
+    use std::path::PathBuf;
+
+    use super::{parse_address, Address};
+    use crate::types::ClientError;
+
+    #[test]
+    fn parse_valid_address() {
+        assert_eq!(
+            Address::Unix(PathBuf::from(
+                "/run/user/1000/speech-dispatcher/speechd.sock"
+            )),
+            parse_address("unix_socket:/run/user/1000/speech-dispatcher/speechd.sock").unwrap()
+        );
+        assert_eq!(
+            Address::Inet(String::from("localhost"), 6561),
+            parse_address("inet_socket:localhost:6561").unwrap()
+        );
+        assert_eq!(
+            Address::Inet(String::from("127.0.0.1"), 6560),
+            parse_address("inet_socket:127.0.0.1").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_invalid_address() {
+        for address in [
+            "",
+            "/run/speechd.sock",
+            "unix_socket:",
+            "inet_socket:",
+            "inet_socket::6560",
+            "inet_socket:localhost:port",
+            "inet_socket:localhost:65536",
+            "tcp:localhost:6560",
+        ] {
+            match parse_address(address) {
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidInput => (),
+                _ => panic!("expecting error 'invalid input' parsing {:?}", address),
+            }
+        }
+    }
+}
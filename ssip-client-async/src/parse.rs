@@ -0,0 +1,452 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2021-2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Parsing of the SSIP answers without I/O.
+//!
+//! The clients read lines from a stream and build on these functions. They can be reused
+//! with other transports.
+
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::constants::{EVENT_INDEX_MARK, OK_CLIENT_ID_SENT, OK_CLIENT_NAME_SET};
+use crate::types::{
+    ClientError, ClientId, ClientResult, ClientStatus, Event, EventId, EventType, Handshake,
+    ReturnCode, StatusLine, SynthesisVoice,
+};
+
+/// Return the only string in the list or an error if there is no line or too many.
+pub(crate) fn parse_single_value(lines: &[String]) -> ClientResult<String> {
+    match lines.len() {
+        0 => Err(ClientError::TooFewLines {
+            expected: 1,
+            got: 0,
+        }),
+        1 => Ok(lines[0].to_string()),
+        n => Err(ClientError::TooManyLines {
+            expected: 1,
+            got: n,
+        }),
+    }
+}
+
+/// Convert two lines of the response in an event id
+pub(crate) fn parse_event_id(lines: &[String]) -> ClientResult<EventId> {
+    match lines.len() {
+        n @ (0 | 1) => Err(ClientError::TooFewLines {
+            expected: 2,
+            got: n,
+        }),
+        2 => Ok(EventId::new(&lines[0], &lines[1])),
+        n => Err(ClientError::TooManyLines {
+            expected: 2,
+            got: n,
+        }),
+    }
+}
+
+/// Convert the status and the data lines of an event.
+pub fn parse_event(status: &StatusLine, lines: &[String]) -> ClientResult<Event> {
+    if lines.len() < 2 {
+        return Err(ClientError::unexpected_eof("event truncated"));
+    }
+    if status.code == EVENT_INDEX_MARK && lines.len() != 3 {
+        return Err(ClientError::unexpected_eof("index mark event truncated"));
+    }
+    EventType::from_code(status.code, lines.get(2).map(String::as_str))
+        .map(|ntype| Event::new(ntype, &lines[0], &lines[1]))
+        .ok_or_else(|| ClientError::invalid_data("wrong status code for event"))
+}
+
+/// Parse single integer value
+pub(crate) fn parse_single_integer<T>(lines: &[String]) -> ClientResult<T>
+where
+    T: FromStr,
+{
+    parse_single_value(lines)?.parse::<T>().map_err(|_| {
+        ClientError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid integer value",
+        ))
+    })
+}
+
+/// Extract the client id from the answer to `SET self CLIENT_NAME`.
+///
+/// The id is either sent on a data line (`208-42`) or appended to the status message
+/// (`208 OK CLIENT NAME SET 42`). Servers that don't send it return `None`.
+pub(crate) fn parse_client_name_reply(status: &StatusLine, lines: &[String]) -> Option<ClientId> {
+    lines
+        .first()
+        .and_then(|line| line.trim().parse::<ClientId>().ok())
+        .or_else(|| {
+            status
+                .message
+                .rsplit(' ')
+                .next()
+                .and_then(|token| token.parse::<ClientId>().ok())
+        })
+}
+
+/// Parse the answer to `SET self CLIENT_NAME`.
+pub fn parse_handshake(status: &StatusLine, lines: &[String]) -> Handshake {
+    Handshake {
+        code: status.code,
+        message: status.message.clone(),
+        client_id: parse_client_name_reply(status, lines),
+    }
+}
+
+/// Parse the client id from either the `CLIENT_NAME` reply or the `HISTORY GET CLIENT_ID` answer.
+pub(crate) fn parse_client_id(status: &StatusLine, lines: &[String]) -> ClientResult<ClientId> {
+    match status.code {
+        OK_CLIENT_ID_SENT => parse_single_value(lines)?
+            .parse::<ClientId>()
+            .map_err(|_| ClientError::invalid_data("invalid client id")),
+        OK_CLIENT_NAME_SET => parse_client_name_reply(status, lines)
+            .ok_or_else(|| ClientError::invalid_data("missing client id")),
+        code => Err(ClientError::UnexpectedStatus(code)),
+    }
+}
+
+/// Extract the log directory from the answer to `SET all DEBUG on`.
+///
+/// The path is either sent on a data line or follows the status message.
+pub(crate) fn parse_debug_path(status: &StatusLine, lines: &[String]) -> Option<PathBuf> {
+    const MSG_DEBUG_SET: &str = "DEBUGGING SET";
+    match lines.first() {
+        Some(line) => Some(PathBuf::from(line.trim())),
+        None => status
+            .message
+            .strip_prefix(MSG_DEBUG_SET)
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+    }
+}
+
+pub(crate) fn parse_typed_lines<T>(lines: &[String]) -> ClientResult<Vec<T>>
+where
+    T: FromStr,
+    ClientError: From<T::Err>,
+{
+    lines
+        .iter()
+        .map(|line| T::from_str(line.as_str()).map_err(ClientError::from))
+        .collect::<ClientResult<Vec<T>>>()
+}
+
+/// Lines that couldn't be parsed, with their error.
+pub type InvalidLines = Vec<(String, ClientError)>;
+
+/// Parse lines, skipping the invalid ones which are returned with their error.
+pub(crate) fn parse_typed_lines_lenient<T>(lines: &[String]) -> (Vec<T>, InvalidLines)
+where
+    T: FromStr,
+    ClientError: From<T::Err>,
+{
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for line in lines.iter() {
+        match T::from_str(line.as_str()) {
+            Ok(value) => values.push(value),
+            Err(err) => errors.push((line.clone(), ClientError::from(err))),
+        }
+    }
+    (values, errors)
+}
+
+/// Parse the value of a speech parameter such as [`Rate`](crate::types::Rate).
+pub(crate) fn parse_speech_parameter<T>(value: &str) -> ClientResult<T>
+where
+    T: TryFrom<i8, Error = ClientError>,
+{
+    value
+        .trim()
+        .parse::<i8>()
+        .map_err(|_| ClientError::invalid_data("invalid speech parameter"))
+        .and_then(T::try_from)
+}
+
+/// Strip prefix if found
+fn strip_prefix(line: &str, prefix: &str) -> String {
+    line.strip_prefix(prefix).unwrap_or(line).to_string()
+}
+
+/// Convert the code and the message "OK msg" or "ERR msg" of a status line.
+fn status_from_code(code: ReturnCode, line: &str) -> ClientStatus {
+    if (300..700).contains(&code) {
+        const TOKEN_ERR: &str = "ERR ";
+        let message = strip_prefix(line, TOKEN_ERR);
+        Err(ClientError::Ssip(StatusLine { code, message }))
+    } else {
+        const TOKEN_OK: &str = "OK ";
+        let message = strip_prefix(line, TOKEN_OK);
+        Ok(StatusLine { code, message })
+    }
+}
+
+/// Parse a status line such as `208 OK CLIENT NAME SET`.
+///
+/// Codes from 300 to 699 are errors returned as [`ClientError::Ssip`]. The line ending is ignored.
+pub fn parse_status_line(line: &str) -> ClientStatus {
+    let line = line.trim_end();
+    match line.get(3..4) {
+        Some(" ") => match line[0..3].parse::<ReturnCode>() {
+            Ok(code) => status_from_code(code, &line[4..]),
+            Err(err) => Err(invalid_input!(err.to_string())),
+        },
+        Some(ch) => Err(invalid_input!("expecting space, got {}.", ch)),
+        None => Err(invalid_input!("line too short: {}", line)),
+    }
+}
+
+/// Parse a line of the answer to `LIST SYNTHESIS_VOICES`.
+pub fn parse_voice(line: &str) -> ClientResult<SynthesisVoice> {
+    SynthesisVoice::from_str(line)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+
+    use super::{parse_status_line, parse_voice, ClientError, ClientResult};
+
+    use crate::types::{StatusLine, SynthesisVoice};
+
+    #[test]
+    fn parse_status_lines() -> ClientResult<()> {
+        let status = parse_status_line("208 OK CLIENT NAME SET\r\n")?;
+        assert_eq!(208, status.code);
+        assert_eq!("CLIENT NAME SET", status.message);
+        match parse_status_line("409 ERR RATE TOO HIGH") {
+            Err(ClientError::Ssip(status)) => {
+                assert_eq!(409, status.code);
+                assert_eq!("RATE TOO HIGH", status.message);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(matches!(
+            parse_status_line("208-42"),
+            Err(ClientError::Io(_))
+        ));
+        assert!(matches!(parse_status_line("20"), Err(ClientError::Io(_))));
+        assert!(matches!(
+            parse_status_line("abc OK"),
+            Err(ClientError::Io(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_voice_line() -> ClientResult<()> {
+        let voice = parse_voice("lancashire\ten\tuk-north")?;
+        assert_eq!(
+            SynthesisVoice::new("lancashire", Some("en"), Some("uk-north")),
+            voice
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_single_value() -> ClientResult<()> {
+        let no_lines = Vec::new();
+        assert!(matches!(
+            super::parse_single_value(&no_lines),
+            Err(ClientError::TooFewLines {
+                expected: 1,
+                got: 0
+            })
+        ));
+
+        let one = String::from("one");
+        let one_line = vec![one.to_owned()];
+        assert_eq!(one, super::parse_single_value(&one_line)?);
+
+        let two_lines = vec![one, String::from("two")];
+        assert!(matches!(
+            super::parse_single_value(&two_lines),
+            Err(ClientError::TooManyLines {
+                expected: 1,
+                got: 2
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_line_count_error() {
+        assert_eq!(
+            "Too many lines: expected 1, got 3",
+            ClientError::TooManyLines {
+                expected: 1,
+                got: 3
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn parse_event_id() -> ClientResult<()> {
+        let no_lines = Vec::new();
+        assert!(matches!(
+            super::parse_event_id(&no_lines),
+            Err(ClientError::TooFewLines {
+                expected: 2,
+                got: 0
+            })
+        ));
+
+        let one_line = vec![String::from("one")];
+        assert!(matches!(
+            super::parse_event_id(&one_line),
+            Err(ClientError::TooFewLines {
+                expected: 2,
+                got: 1
+            })
+        ));
+
+        let mid = String::from("message");
+        let cid = String::from("client");
+        let two_lines = vec![mid.to_owned(), cid.to_owned()];
+        let event_id = super::parse_event_id(&two_lines)?;
+        assert_eq!(mid, event_id.message);
+        assert_eq!(cid, event_id.client);
+
+        let three_lines = vec![
+            String::from("one"),
+            String::from("two"),
+            String::from("three"),
+        ];
+        assert!(matches!(
+            super::parse_event_id(&three_lines),
+            Err(ClientError::TooManyLines {
+                expected: 2,
+                got: 3
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_synthesis_voices() -> ClientResult<()> {
+        let lines = ["en", "afrikaans\taf", "lancashire\ten\tuk-north"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let voices = super::parse_typed_lines::<SynthesisVoice>(&lines)?;
+        assert_eq!(3, voices.len());
+        assert_eq!("en", voices[0].name.as_str());
+        assert_eq!(Some(String::from("af")), voices[1].language);
+        assert_eq!(Some(String::from("uk-north")), voices[2].dialect);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_synthesis_voices_lenient() {
+        let lines = ["en", "\tfr", "afrikaans\taf"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        assert!(super::parse_typed_lines::<SynthesisVoice>(&lines).is_err());
+        let (voices, errors) = super::parse_typed_lines_lenient::<SynthesisVoice>(&lines);
+        assert_eq!(
+            vec!["en", "afrikaans"],
+            voices
+                .iter()
+                .map(|v| v.name.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(1, errors.len());
+        assert_eq!("\tfr", errors[0].0);
+    }
+
+    #[test]
+    fn parse_client_name_reply() {
+        let status = StatusLine {
+            code: 208,
+            message: String::from("CLIENT NAME SET"),
+        };
+        assert_eq!(None, super::parse_client_name_reply(&status, &[]));
+        assert_eq!(
+            Some(42),
+            super::parse_client_name_reply(&status, &[String::from("42")])
+        );
+        let status_with_id = StatusLine {
+            code: 208,
+            message: String::from("CLIENT NAME SET 17"),
+        };
+        assert_eq!(
+            Some(17),
+            super::parse_client_name_reply(&status_with_id, &[])
+        );
+    }
+
+    #[test]
+    fn parse_client_id() -> ClientResult<()> {
+        let id_sent = StatusLine {
+            code: 245,
+            message: String::from("CLIENT ID SENT"),
+        };
+        assert_eq!(
+            123,
+            super::parse_client_id(&id_sent, &[String::from("123")])?
+        );
+        assert!(matches!(
+            super::parse_client_id(&id_sent, &[String::from("abc")]),
+            Err(ClientError::Io(_))
+        ));
+        let name_set = StatusLine {
+            code: 208,
+            message: String::from("CLIENT NAME SET"),
+        };
+        assert_eq!(
+            42,
+            super::parse_client_id(&name_set, &[String::from("42")])?
+        );
+        assert!(matches!(
+            super::parse_client_id(&name_set, &[]),
+            Err(ClientError::Io(_))
+        ));
+        let other = StatusLine {
+            code: 251,
+            message: String::from("GET RETURNED"),
+        };
+        assert!(matches!(
+            super::parse_client_id(&other, &[]),
+            Err(ClientError::UnexpectedStatus(251))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_debug_path() {
+        let status = StatusLine {
+            code: 262,
+            message: String::from("DEBUGGING SET"),
+        };
+        assert_eq!(None, super::parse_debug_path(&status, &[]));
+        assert_eq!(
+            Some(PathBuf::from("/tmp/speechd-debug")),
+            super::parse_debug_path(&status, &[String::from("/tmp/speechd-debug")])
+        );
+        let status_with_path = StatusLine {
+            code: 262,
+            message: String::from("DEBUGGING SET /tmp/speechd-debug"),
+        };
+        assert_eq!(
+            Some(PathBuf::from("/tmp/speechd-debug")),
+            super::parse_debug_path(&status_with_path, &[])
+        );
+    }
+}
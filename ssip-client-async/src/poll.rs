@@ -8,7 +8,7 @@
 // modified, or distributed except according to those terms.
 
 use std::collections::VecDeque;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 
 use crate::{
     client::{Client, Source},
@@ -27,6 +27,16 @@ mod mio {
     ///
     /// See [`mio::Token`](https://docs.rs/mio/latest/mio/struct.Token.html#).
     pub struct Token(pub usize);
+
+    /// Interest used in registering.
+    ///
+    /// See [`mio::Interest`](https://docs.rs/mio/latest/mio/struct.Interest.html#).
+    pub struct Interest {}
+
+    /// Registers I/O resources.
+    ///
+    /// See [`mio::Registry`](https://docs.rs/mio/latest/mio/struct.Registry.html#).
+    pub struct Registry {}
 }
 
 const INITIAL_REQUEST_QUEUE_CAPACITY: usize = 4;
@@ -37,9 +47,23 @@ const INITIAL_REQUEST_QUEUE_CAPACITY: usize = 4;
 /// with [mio](https://crates.io/crates/mio) if feature `async-mio` is enabled.
 ///
 /// When the output is ready, a next event can be sent.
+///
+/// Like [`Client`], it is `Send` and `Sync` when the stream is.
+///
+/// ```no_run
+/// use ssip_client_async::{fifo, QueuedClient, Request, Response};
+/// let mut client = QueuedClient::new(fifo::Builder::new().build()?);
+/// client.push(Request::Speak);
+/// client.push(Request::SendLine(String::from("hello")));
+/// while client.send_next()? {}
+/// assert_eq!(Response::ReceivingData, client.receive_next()?);
+/// assert_eq!(Response::MessageQueued, client.receive_next()?);
+/// # Ok::<(), ssip_client_async::ClientError>(())
+/// ```
 pub struct QueuedClient<S: Read + Write + Source> {
     client: Client<S>,
     requests: VecDeque<Request>,
+    in_data: bool,
 }
 
 impl<S: Read + Write + Source> QueuedClient<S> {
@@ -48,6 +72,7 @@ impl<S: Read + Write + Source> QueuedClient<S> {
         Self {
             client,
             requests: VecDeque::with_capacity(INITIAL_REQUEST_QUEUE_CAPACITY),
+            in_data: false,
         }
     }
 
@@ -74,6 +99,58 @@ impl<S: Read + Write + Source> QueuedClient<S> {
         self.client.register(poll, input_token, output_token)
     }
 
+    #[cfg(any(feature = "async-mio", doc))]
+    /// Register client in a registry
+    ///
+    /// Unlike `mio::Poll`, a registry obtained with `poll.registry().try_clone()` is `Send`,
+    /// so the client can be registered from another thread than the one polling.
+    pub fn register_with(
+        &mut self,
+        registry: &mio::Registry,
+        input_token: mio::Token,
+        output_token: mio::Token,
+    ) -> std::io::Result<()> {
+        self.client
+            .register_with(registry, input_token, output_token)
+    }
+
+    #[cfg(any(feature = "async-mio", doc))]
+    /// Register client with a single token and the given interest
+    ///
+    /// A listen-only client can register with `mio::Interest::READABLE` to avoid writable
+    /// events.
+    pub fn register_interest(
+        &mut self,
+        poll: &mio::Poll,
+        token: mio::Token,
+        interest: mio::Interest,
+    ) -> std::io::Result<()> {
+        self.client.register_interest(poll, token, interest)
+    }
+
+    #[cfg(any(feature = "async-mio", doc))]
+    /// Reregister client with other tokens
+    ///
+    /// To replace the connection, deregister the old client, then connect and register
+    /// the new one.
+    pub fn reregister(
+        &mut self,
+        poll: &mio::Poll,
+        input_token: mio::Token,
+        output_token: mio::Token,
+    ) -> std::io::Result<()> {
+        self.client.reregister(poll, input_token, output_token)
+    }
+
+    #[cfg(any(feature = "async-mio", doc))]
+    /// Deregister client
+    ///
+    /// The client must be deregistered before it's dropped if the poll is reused for
+    /// another connection.
+    pub fn deregister(&mut self, poll: &mio::Poll) -> std::io::Result<()> {
+        self.client.deregister(poll)
+    }
+
     /// Push a new request in the queue.
     pub fn push(&mut self, request: Request) {
         self.requests.push_back(request);
@@ -89,6 +166,40 @@ impl<S: Read + Write + Source> QueuedClient<S> {
         self.requests.back()
     }
 
+    /// Next request to be sent by [`QueuedClient::send_next`].
+    pub fn peek_next(&self) -> Option<&Request> {
+        self.requests.front()
+    }
+
+    /// Discard all the pending requests and return how many were discarded.
+    ///
+    /// If `SPEAK` was sent but not its text, the server is waiting for data: the text is
+    /// kept, or an empty text is queued to end the block if it wasn't pushed yet.
+    pub fn clear(&mut self) -> usize {
+        let data = if self.in_data {
+            match self.requests.pop_front() {
+                Some(request @ (Request::SendLine(_) | Request::SendLines(_))) => Some(request),
+                _ => Some(Request::SendLines(Vec::new())),
+            }
+        } else {
+            None
+        };
+        let count = self.requests.len();
+        self.requests.clear();
+        self.requests.extend(data);
+        count
+    }
+
+    /// Discard all the pending requests and queue `STOP all` to be sent next.
+    ///
+    /// As with [`QueuedClient::clear`], the text of a message whose `SPEAK` was already sent
+    /// is sent first. Return the number of requests discarded.
+    pub fn cancel_all(&mut self) -> usize {
+        let count = self.clear();
+        self.requests.push_back(Request::Stop(MessageScope::All));
+        count
+    }
+
     /// Return true if there is a pending request.
     pub fn has_next(&self) -> bool {
         !self.requests.is_empty()
@@ -100,7 +211,13 @@ impl<S: Read + Write + Source> QueuedClient<S> {
     /// This error is mapped to `ClientError::NotReady`.
     pub fn send_next(&mut self) -> ClientResult<bool> {
         if let Some(request) = self.requests.pop_front() {
+            let in_data = match request {
+                Request::Speak => true,
+                Request::SendLine(_) | Request::SendLines(_) => false,
+                _ => self.in_data,
+            };
             self.client.send(request)?;
+            self.in_data = in_data;
             Ok(true)
         } else {
             Ok(false)
@@ -113,4 +230,21 @@ impl<S: Read + Write + Source> QueuedClient<S> {
     pub fn receive_next(&mut self) -> ClientResult<Response> {
         self.client.receive()
     }
+
+    /// Receive all the responses and events already available.
+    ///
+    /// Reading stops when the socket would block. Events are returned as
+    /// `Response::Event*` in the order they were received.
+    pub fn receive_all(&mut self) -> ClientResult<Vec<Response>> {
+        let mut responses = Vec::new();
+        loop {
+            match self.client.receive() {
+                Ok(response) => responses.push(response),
+                Err(ClientError::NotReady) => break,
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(responses)
+    }
 }
@@ -8,18 +8,19 @@
 // modified, or distributed except according to those terms.
 
 use log::debug;
+use std::borrow::Cow;
 use std::io::{self, BufRead, Write};
 
-#[cfg(any(feature = "async-std", doc))]
-use async_std::io::{
-    prelude::BufReadExt, BufRead as AsyncBufReadStd, Write as AsyncWriteStd, WriteExt,
+#[cfg(any(feature = "async-std", feature = "async-io"))]
+use futures_util::io::{
+    AsyncBufRead as AsyncBufReadStd, AsyncBufReadExt as _, AsyncWrite as AsyncWriteStd,
+    AsyncWriteExt as _,
 };
-#[cfg(any(feature = "tokio", doc))]
+#[cfg(feature = "tokio")]
 use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
 
-use std::str::FromStr;
-
-use crate::types::{ClientError, ClientResult, ClientStatus, EventId, StatusLine};
+use crate::parse::parse_status_line;
+use crate::types::{ClientError, ClientResult, ClientStatus, StatusLine};
 
 macro_rules! invalid_input {
     ($msg:expr) => {
@@ -30,59 +31,71 @@ macro_rules! invalid_input {
     };
 }
 
-/// Return the only string in the list or an error if there is no line or too many.
-pub(crate) fn parse_single_value(lines: &[String]) -> ClientResult<String> {
-    match lines.len() {
-        0 => Err(ClientError::TooFewLines),
-        1 => Ok(lines[0].to_string()),
-        _ => Err(ClientError::TooManyLines),
+/// Write lines separated by CRLF.
+pub(crate) fn write_lines<W: Write + ?Sized>(output: &mut W, lines: &[&str]) -> ClientResult<()> {
+    for line in lines.iter() {
+        debug!("SSIP(out): {}", line);
+        output.write_all(line.as_bytes())?;
+        output.write_all(b"\r\n")?;
     }
+    Ok(())
 }
 
-/// Convert two lines of the response in an event id
-pub(crate) fn parse_event_id(lines: &[String]) -> ClientResult<EventId> {
-    match lines.len() {
-        0 | 1 => Err(ClientError::TooFewLines),
-        2 => Ok(EventId::new(&lines[0], &lines[1])),
-        _ => Err(ClientError::TooManyLines),
+/// Escape a line of a data block.
+///
+/// A line starting with a dot is escaped with a second dot, so that a line made of a single
+/// dot in the text doesn't end the block early.
+pub(crate) fn escape_data_line(line: &str) -> Cow<'_, str> {
+    if line.starts_with('.') {
+        Cow::Owned(format!(".{}", line))
+    } else {
+        Cow::Borrowed(line)
     }
 }
 
-/// Parse single integer value
-pub(crate) fn parse_single_integer<T>(lines: &[String]) -> ClientResult<T>
-where
-    T: FromStr,
-{
-    parse_single_value(lines)?.parse::<T>().map_err(|_| {
-        ClientError::Io(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "invalid integer value",
-        ))
-    })
+/// Encode lines of text as a SSIP data block, as sent after `SPEAK`.
+///
+/// Lines are escaped, terminated by CRLF and the block ends with a line made of a single dot.
+pub fn encode_data_block(lines: &[&str]) -> String {
+    let mut block = String::new();
+    for line in lines.iter() {
+        block.push_str(&escape_data_line(line));
+        block.push_str("\r\n");
+    }
+    block.push_str(".\r\n");
+    block
 }
 
-pub(crate) fn parse_typed_lines<T>(lines: &[String]) -> ClientResult<Vec<T>>
-where
-    T: FromStr<Err = ClientError>,
-{
-    lines
-        .iter()
-        .map(|line| T::from_str(line.as_str()))
-        .collect::<ClientResult<Vec<T>>>()
+/// Split a text in lines separated by LF or CRLF.
+pub(crate) fn text_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
 }
 
-/// Write lines separated by CRLF.
-pub(crate) fn write_lines<W: Write + ?Sized>(output: &mut W, lines: &[&str]) -> ClientResult<()> {
-    for line in lines.iter() {
-        debug!("SSIP(out): {}", line);
-        output.write_all(line.as_bytes())?;
-        output.write_all(b"\r\n")?;
+/// Write lines as a data block, with the same escaping as [`encode_data_block`].
+pub(crate) fn write_data_block<'a, W: Write + ?Sized>(
+    output: &mut W,
+    lines: impl IntoIterator<Item = &'a str>,
+) -> ClientResult<()> {
+    for line in lines {
+        write_lines(output, &[&escape_data_line(line)])?;
     }
+    write_lines(output, &["."])
+}
+
+/// Write a text as a data block and flush the output.
+///
+/// The text must be UTF-8. Lines are written one by one to the output, that is expected to
+/// be buffered, without copying the whole text.
+pub(crate) fn write_text_bytes<W: Write + ?Sized>(output: &mut W, text: &[u8]) -> ClientResult<()> {
+    let text = std::str::from_utf8(text).map_err(|_| invalid_input!("text is not valid UTF-8"))?;
+    write_data_block(output, text_lines(text))?;
+    output.flush()?;
     Ok(())
 }
 
 /// Write lines (asyncronously) separated by CRLF.
-#[cfg(any(feature = "tokio", doc))]
+#[cfg(feature = "tokio")]
 pub(crate) async fn write_lines_tokio<W: AsyncWrite + Unpin + ?Sized>(
     output: &mut W,
     lines: &[&str],
@@ -95,7 +108,7 @@ pub(crate) async fn write_lines_tokio<W: AsyncWrite + Unpin + ?Sized>(
     Ok(())
 }
 /// Write lines (asyncronously) separated by CRLF.
-#[cfg(any(feature = "async-std", doc))]
+#[cfg(any(feature = "async-std", feature = "async-io"))]
 pub(crate) async fn write_lines_async_std<W: AsyncWriteStd + Unpin + ?Sized>(
     output: &mut W,
     lines: &[&str],
@@ -108,14 +121,8 @@ pub(crate) async fn write_lines_async_std<W: AsyncWriteStd + Unpin + ?Sized>(
     Ok(())
 }
 
-/// Write lines separated by CRLF and flush the output.
-pub(crate) fn flush_lines<W: Write + ?Sized>(output: &mut W, lines: &[&str]) -> ClientResult<()> {
-    write_lines(output, lines)?;
-    output.flush()?;
-    Ok(())
-}
 /// Write lines separated by CRLF and flush the output asyncronously.
-#[cfg(any(feature = "tokio", doc))]
+#[cfg(feature = "tokio")]
 pub(crate) async fn flush_lines_tokio<W: AsyncWrite + Unpin + ?Sized>(
     output: &mut W,
     lines: &[&str],
@@ -125,7 +132,7 @@ pub(crate) async fn flush_lines_tokio<W: AsyncWrite + Unpin + ?Sized>(
     Ok(())
 }
 /// Write lines separated by CRLF and flush the output asyncronously.
-#[cfg(any(feature = "async-std", doc))]
+#[cfg(any(feature = "async-std", feature = "async-io"))]
 pub(crate) async fn flush_lines_async_std<W: AsyncWriteStd + Unpin + ?Sized>(
     output: &mut W,
     lines: &[&str],
@@ -135,108 +142,197 @@ pub(crate) async fn flush_lines_async_std<W: AsyncWriteStd + Unpin + ?Sized>(
     Ok(())
 }
 
-/// Strip prefix if found
-fn strip_prefix(line: &str, prefix: &str) -> String {
-    line.strip_prefix(prefix).unwrap_or(line).to_string()
+/// Write lines as a data block and flush the output asyncronously.
+#[cfg(feature = "tokio")]
+pub(crate) async fn flush_data_block_tokio<'a, W: AsyncWrite + Unpin + ?Sized>(
+    output: &mut W,
+    lines: impl IntoIterator<Item = &'a str>,
+) -> ClientResult<()> {
+    for line in lines {
+        write_lines_tokio(output, &[&escape_data_line(line)]).await?;
+    }
+    flush_lines_tokio(output, &["."]).await
+}
+/// Write lines as a data block and flush the output asyncronously.
+#[cfg(any(feature = "async-std", feature = "async-io"))]
+pub(crate) async fn flush_data_block_async_std<'a, W: AsyncWriteStd + Unpin + ?Sized>(
+    output: &mut W,
+    lines: impl IntoIterator<Item = &'a str>,
+) -> ClientResult<()> {
+    for line in lines {
+        write_lines_async_std(output, &[&escape_data_line(line)]).await?;
+    }
+    flush_lines_async_std(output, &["."]).await
 }
 
-/// Parse the status line "OK msg" or "ERR msg"
-fn parse_status_line(code: u16, line: &str) -> ClientStatus {
-    if (300..700).contains(&code) {
-        const TOKEN_ERR: &str = "ERR ";
-        let message = strip_prefix(line, TOKEN_ERR);
-        Err(ClientError::Ssip(StatusLine { code, message }))
-    } else {
-        const TOKEN_OK: &str = "OK ";
-        let message = strip_prefix(line, TOKEN_OK);
-        Ok(StatusLine { code, message })
+/// Answer being read, kept between calls when reading is interrupted.
+///
+/// Bytes of an incomplete line and data lines already read are not lost when a read fails,
+/// for example with `WouldBlock`, so the next read continues where it stopped.
+#[derive(Debug, Default)]
+pub(crate) struct PartialAnswer {
+    line: Vec<u8>,
+    lines: Vec<String>,
+    lenient: bool,
+}
+
+impl PartialAnswer {
+    /// Accept lines terminated by a bare LF instead of CRLF.
+    pub(crate) fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Parse the line read so far and return the status when the answer is complete.
+    ///
+    /// Once the answer is complete or invalid, data lines are moved to `lines`.
+    fn next_status(
+        &mut self,
+        lines: &mut Option<&mut Vec<String>>,
+    ) -> ClientResult<Option<StatusLine>> {
+        let result = self
+            .take_line()
+            .and_then(|bytes| parse_answer_line(bytes, &mut self.lines, lines.is_some()));
+        if !matches!(result, Ok(None)) {
+            match lines {
+                Some(lines) => lines.append(&mut self.lines),
+                None => self.lines.clear(),
+            }
+        }
+        result
+    }
+
+    /// Same as [`PartialAnswer::next_status`], except that the lines are returned without
+    /// terminator and without being parsed, the status line included.
+    fn next_raw_answer(&mut self) -> ClientResult<Option<Vec<String>>> {
+        let result = self.take_line().and_then(|bytes| {
+            let line =
+                String::from_utf8(bytes).map_err(|_| ClientError::invalid_data("invalid UTF-8"))?;
+            debug!("SSIP(in): {}", line.trim_end());
+            let line = line.trim_end_matches(['\r', '\n']).to_string();
+            let is_status = line.as_bytes().get(3) != Some(&b'-');
+            self.lines.push(line);
+            Ok(is_status)
+        });
+        match result {
+            Ok(false) => Ok(None),
+            Ok(true) => Ok(Some(std::mem::take(&mut self.lines))),
+            Err(err) => {
+                self.lines.clear();
+                Err(err)
+            }
+        }
+    }
+
+    /// Take the line read so far, checking that it's terminated.
+    ///
+    /// A line without terminator was cut by the end of the stream. If nothing was read
+    /// since the previous answer, the server closed the connection cleanly.
+    fn take_line(&mut self) -> ClientResult<Vec<u8>> {
+        let bytes = std::mem::take(&mut self.line);
+        if !bytes.ends_with(b"\n") {
+            if bytes.is_empty() && self.lines.is_empty() {
+                Err(ClientError::Disconnected)
+            } else {
+                Err(ClientError::unexpected_eof("answer truncated"))
+            }
+        } else if !self.lenient && !bytes.ends_with(b"\r\n") {
+            Err(ClientError::invalid_data("line not terminated by CRLF"))
+        } else {
+            Ok(bytes)
+        }
+    }
+}
+
+/// Read the lines of an answer up to the status line, without parsing them.
+///
+/// Line terminators are removed. Error status lines are returned like the others. As for
+/// [`receive_answer`], an interrupted read continues where it stopped on the next call.
+pub(crate) fn receive_raw_answer<R: BufRead + ?Sized>(
+    input: &mut R,
+    partial: &mut PartialAnswer,
+) -> ClientResult<Vec<String>> {
+    loop {
+        input
+            .read_until(b'\n', &mut partial.line)
+            .map_err(ClientError::Io)?;
+        if let Some(lines) = partial.next_raw_answer()? {
+            return Ok(lines);
+        }
+    }
+}
+
+/// Parse a line of an answer. Data lines are pushed to `data` if `collect` is true.
+fn parse_answer_line(
+    bytes: Vec<u8>,
+    data: &mut Vec<String>,
+    collect: bool,
+) -> ClientResult<Option<StatusLine>> {
+    let line = String::from_utf8(bytes).map_err(|_| ClientError::invalid_data("invalid UTF-8"))?;
+    debug!("SSIP(in): {}", line.trim_end());
+    match line.chars().nth(3) {
+        Some(ch) => match ch {
+            ' ' => parse_status_line(&line).map(Some),
+            '-' if collect => {
+                data.push(line[4..].trim_end().to_string());
+                Ok(None)
+            }
+            '-' => Err(invalid_input!("unexpected line: {}", line)),
+            ch => Err(invalid_input!("expecting space or dash, got {}.", ch)),
+        },
+        None if line.is_empty() => Err(invalid_input!("empty line")),
+        None => Err(invalid_input!("line too short: {}", line)),
     }
 }
 
 /// Read lines from server until a status line is found.
-#[cfg(any(feature = "tokio", doc))]
+#[cfg(feature = "tokio")]
 pub(crate) async fn receive_answer_tokio<W: AsyncBufRead + Unpin + ?Sized>(
     input: &mut W,
+    partial: &mut PartialAnswer,
     mut lines: Option<&mut Vec<String>>,
 ) -> ClientStatus {
     loop {
-        let mut line = String::new();
-        input.read_line(&mut line).await.map_err(ClientError::Io)?;
-        debug!("SSIP(in): {}", line.trim_end());
-        match line.chars().nth(3) {
-            Some(ch) => match ch {
-                ' ' => match line[0..3].parse::<u16>() {
-                    Ok(code) => return parse_status_line(code, line[4..].trim_end()),
-                    Err(err) => return Err(invalid_input!(err.to_string())),
-                },
-                '-' => match lines {
-                    Some(ref mut lines) => lines.push(line[4..].trim_end().to_string()),
-                    None => return Err(invalid_input!("unexpected line: {}", line)),
-                },
-                ch => {
-                    return Err(invalid_input!("expecting space or dash, got {}.", ch));
-                }
-            },
-            None if line.is_empty() => return Err(invalid_input!("empty line")),
-            None => return Err(invalid_input!("line too short: {}", line)),
+        input
+            .read_until(b'\n', &mut partial.line)
+            .await
+            .map_err(ClientError::Io)?;
+        if let Some(status) = partial.next_status(&mut lines)? {
+            return Ok(status);
         }
     }
 }
 /// Read lines from server until a status line is found.
-#[cfg(any(feature = "async-std", doc))]
+#[cfg(any(feature = "async-std", feature = "async-io"))]
 pub(crate) async fn receive_answer_async_std<W: AsyncBufReadStd + Unpin + ?Sized>(
     input: &mut W,
+    partial: &mut PartialAnswer,
     mut lines: Option<&mut Vec<String>>,
 ) -> ClientStatus {
     loop {
-        let mut line = String::new();
-        input.read_line(&mut line).await.map_err(ClientError::Io)?;
-        debug!("SSIP(in): {}", line.trim_end());
-        match line.chars().nth(3) {
-            Some(ch) => match ch {
-                ' ' => match line[0..3].parse::<u16>() {
-                    Ok(code) => return parse_status_line(code, line[4..].trim_end()),
-                    Err(err) => return Err(invalid_input!(err.to_string())),
-                },
-                '-' => match lines {
-                    Some(ref mut lines) => lines.push(line[4..].trim_end().to_string()),
-                    None => return Err(invalid_input!("unexpected line: {}", line)),
-                },
-                ch => {
-                    return Err(invalid_input!("expecting space or dash, got {}.", ch));
-                }
-            },
-            None if line.is_empty() => return Err(invalid_input!("empty line")),
-            None => return Err(invalid_input!("line too short: {}", line)),
+        input
+            .read_until(b'\n', &mut partial.line)
+            .await
+            .map_err(ClientError::Io)?;
+        if let Some(status) = partial.next_status(&mut lines)? {
+            return Ok(status);
         }
     }
 }
 
-/// Read lines from server until a status line is found asyncronously.
+/// Read lines from server until a status line is found.
+///
+/// Like writes, reads interrupted by a signal are retried, other errors are returned.
 pub(crate) fn receive_answer<W: BufRead + ?Sized>(
     input: &mut W,
+    partial: &mut PartialAnswer,
     mut lines: Option<&mut Vec<String>>,
 ) -> ClientStatus {
     loop {
-        let mut line = String::new();
-        input.read_line(&mut line).map_err(ClientError::Io)?;
-        debug!("SSIP(in): {}", line.trim_end());
-        match line.chars().nth(3) {
-            Some(ch) => match ch {
-                ' ' => match line[0..3].parse::<u16>() {
-                    Ok(code) => return parse_status_line(code, line[4..].trim_end()),
-                    Err(err) => return Err(invalid_input!(err.to_string())),
-                },
-                '-' => match lines {
-                    Some(ref mut lines) => lines.push(line[4..].trim_end().to_string()),
-                    None => return Err(invalid_input!("unexpected line: {}", line)),
-                },
-                ch => {
-                    return Err(invalid_input!("expecting space or dash, got {}.", ch));
-                }
-            },
-            None if line.is_empty() => return Err(invalid_input!("empty line")),
-            None => return Err(invalid_input!("line too short: {}", line)),
+        input
+            .read_until(b'\n', &mut partial.line)
+            .map_err(ClientError::Io)?;
+        if let Some(status) = partial.next_status(&mut lines)? {
+            return Ok(status);
         }
     }
 }
@@ -244,16 +340,17 @@ pub(crate) fn receive_answer<W: BufRead + ?Sized>(
 #[cfg(test)]
 mod tests {
 
-    use std::io::BufReader;
-
-    use super::{receive_answer, ClientError, ClientResult};
+    use std::io::{self, BufReader, Read, Write};
 
-    use crate::types::SynthesisVoice;
+    use super::{
+        encode_data_block, receive_answer, receive_raw_answer, write_text_bytes, ClientError,
+        ClientResult, PartialAnswer,
+    };
 
     #[test]
     fn single_ok_status_line() {
         let mut input = BufReader::new("208 OK CLIENT NAME SET\r\n".as_bytes());
-        let status = receive_answer(&mut input, None).unwrap();
+        let status = receive_answer(&mut input, &mut PartialAnswer::default(), None).unwrap();
         assert_eq!(208, status.code);
         assert_eq!("CLIENT NAME SET", status.message);
     }
@@ -261,7 +358,7 @@ mod tests {
     #[test]
     fn single_success_status_line() {
         let mut input = BufReader::new("231 HAPPY HACKING\r\n".as_bytes());
-        let status = receive_answer(&mut input, None).unwrap();
+        let status = receive_answer(&mut input, &mut PartialAnswer::default(), None).unwrap();
         assert_eq!(231, status.code);
         assert_eq!("HAPPY HACKING", status.message);
     }
@@ -269,7 +366,10 @@ mod tests {
     #[test]
     fn single_err_status_line() {
         let mut input = BufReader::new("409 ERR RATE TOO HIGH\r\n".as_bytes());
-        match receive_answer(&mut input, None).err().unwrap() {
+        match receive_answer(&mut input, &mut PartialAnswer::default(), None)
+            .err()
+            .unwrap()
+        {
             ClientError::Ssip(status) => {
                 assert_eq!(409, status.code);
                 assert_eq!("RATE TOO HIGH", status.message);
@@ -285,7 +385,8 @@ mod tests {
                 .as_bytes(),
         );
         let mut lines = Vec::new();
-        let status = receive_answer(&mut input, Some(&mut lines)).unwrap();
+        let status =
+            receive_answer(&mut input, &mut PartialAnswer::default(), Some(&mut lines)).unwrap();
         assert_eq!(249, status.code);
         assert_eq!("VOICE LIST SENT", status.message);
         assert_eq!(
@@ -295,71 +396,205 @@ mod tests {
     }
 
     #[test]
-    fn parse_single_value() -> ClientResult<()> {
-        let no_lines = Vec::new();
-        assert!(matches!(
-            super::parse_single_value(&no_lines),
-            Err(ClientError::TooFewLines)
-        ));
+    fn continuation_lines() {
+        let mut input = BufReader::new(
+            "216-first - line\r\n216-216 OK NOT FINAL\r\n216 OK TABLE SENT\r\n".as_bytes(),
+        );
+        let mut lines = Vec::new();
+        let status =
+            receive_answer(&mut input, &mut PartialAnswer::default(), Some(&mut lines)).unwrap();
+        assert_eq!(216, status.code);
+        assert_eq!("TABLE SENT", status.message);
+        assert_eq!(vec!["first - line", "216 OK NOT FINAL"], lines.as_slice());
+    }
 
-        let one = String::from("one");
-        let one_line = vec![one.to_owned()];
-        assert_eq!(one, super::parse_single_value(&one_line)?);
+    /// Reader returning chunks of data, `WouldBlock` after each chunk.
+    struct ChunkedReader(Vec<&'static [u8]>);
 
-        let two_lines = vec![one, String::from("two")];
-        assert!(matches!(
-            super::parse_single_value(&two_lines),
-            Err(ClientError::TooManyLines)
-        ));
+    impl Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.first_mut() {
+                Some([]) => {
+                    self.0.remove(0);
+                    Err(io::ErrorKind::WouldBlock.into())
+                }
+                Some(chunk) => {
+                    let len = chunk.len().min(buf.len());
+                    buf[..len].copy_from_slice(&chunk[..len]);
+                    *chunk = &chunk[len..];
+                    Ok(len)
+                }
+                None => Ok(0),
+            }
+        }
+    }
 
-        Ok(())
+    #[test]
+    fn resume_partial_answer() {
+        let mut input = BufReader::new(ChunkedReader(vec![
+            b"249-afrikaans\taf\tnone\r\n249-en-rho",
+            b"tic\ten\tr\r\n249 OK VOICE LIST SENT\r\n",
+        ]));
+        let mut partial = PartialAnswer::default();
+        let mut lines = Vec::new();
+        match receive_answer(&mut input, &mut partial, Some(&mut lines)) {
+            Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(lines.is_empty());
+        let status = receive_answer(&mut input, &mut partial, Some(&mut lines)).unwrap();
+        assert_eq!(249, status.code);
+        assert_eq!(
+            vec!["afrikaans\taf\tnone", "en-rhotic\ten\tr"],
+            lines.as_slice()
+        );
     }
 
     #[test]
-    fn parse_event_id() -> ClientResult<()> {
-        let no_lines = Vec::new();
-        assert!(matches!(
-            super::parse_event_id(&no_lines),
-            Err(ClientError::TooFewLines)
-        ));
+    fn resume_partial_raw_answer() {
+        let mut input = BufReader::new(ChunkedReader(vec![
+            b"249-afrikaans\taf\tnone\n249 OK VOICE",
+            b" LIST SENT\n",
+        ]));
+        let mut partial = PartialAnswer::default();
+        partial.set_lenient(true);
+        match receive_raw_answer(&mut input, &mut partial) {
+            Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!(
+            vec!["249-afrikaans\taf\tnone", "249 OK VOICE LIST SENT"],
+            receive_raw_answer(&mut input, &mut partial).unwrap()
+        );
+        partial.set_lenient(false);
+        let mut input = BufReader::new(b"251 OK\n".as_slice());
+        match receive_raw_answer(&mut input, &mut partial) {
+            Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidData => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
 
-        let one_line = vec![String::from("one")];
-        assert!(matches!(
-            super::parse_event_id(&one_line),
-            Err(ClientError::TooFewLines)
-        ));
+    /// Stream failing with `Interrupted` on every other call, like a blocking call hit by signals.
+    struct InterruptedStream {
+        interrupt: bool,
+        input: &'static [u8],
+        output: Vec<u8>,
+    }
+
+    impl InterruptedStream {
+        fn interrupted(&mut self) -> bool {
+            self.interrupt = !self.interrupt;
+            !self.interrupt
+        }
+    }
+
+    impl Read for InterruptedStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.interrupted() {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let len = self.input.len().min(buf.len()).min(4);
+            buf[..len].copy_from_slice(&self.input[..len]);
+            self.input = &self.input[len..];
+            Ok(len)
+        }
+    }
+
+    impl Write for InterruptedStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.interrupted() {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn retry_interrupted_io() -> ClientResult<()> {
+        let mut stream = InterruptedStream {
+            interrupt: false,
+            input: b"225-21\r\n225 OK MESSAGE QUEUED\r\n",
+            output: Vec::new(),
+        };
+        write_text_bytes(&mut stream, b"hello")?;
+        assert_eq!(b"hello\r\n.\r\n".as_slice(), stream.output.as_slice());
+        let mut lines = Vec::new();
+        let mut input = BufReader::new(stream);
+        let status = receive_answer(&mut input, &mut PartialAnswer::default(), Some(&mut lines))?;
+        assert_eq!(225, status.code);
+        assert_eq!(vec!["21"], lines);
+        Ok(())
+    }
+
+    #[test]
+    fn line_feed_only() {
+        const ANSWER: &str =
+            "249-afrikaans\taf\tnone\n249-en-rhotic\ten\tr\r\n249 OK VOICE LIST SENT\n";
+        let mut partial = PartialAnswer::default();
+        let mut lines = Vec::new();
+        let mut input = BufReader::new(ANSWER.as_bytes());
+        match receive_answer(&mut input, &mut partial, Some(&mut lines)) {
+            Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidData => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        partial.set_lenient(true);
+        let mut input = BufReader::new(ANSWER.as_bytes());
+        let status = receive_answer(&mut input, &mut partial, Some(&mut lines)).unwrap();
+        assert_eq!(249, status.code);
+        assert_eq!("VOICE LIST SENT", status.message);
+        assert_eq!(
+            vec!["afrikaans\taf\tnone", "en-rhotic\ten\tr"],
+            lines.as_slice()
+        );
+    }
 
-        let mid = String::from("message");
-        let cid = String::from("client");
-        let two_lines = vec![mid.to_owned(), cid.to_owned()];
-        let event_id = super::parse_event_id(&two_lines)?;
-        assert_eq!(mid, event_id.message);
-        assert_eq!(cid, event_id.client);
-
-        let three_lines = vec![
-            String::from("one"),
-            String::from("two"),
-            String::from("three"),
-        ];
+    #[test]
+    fn end_of_stream() {
+        let mut partial = PartialAnswer::default();
+        let mut lines = Vec::new();
+        let mut input = BufReader::new(b"".as_slice());
         assert!(matches!(
-            super::parse_event_id(&three_lines),
-            Err(ClientError::TooManyLines)
+            receive_answer(&mut input, &mut partial, Some(&mut lines)),
+            Err(ClientError::Disconnected)
         ));
+        for answer in ["249-en\r\n", "249 OK VOICE LIST"] {
+            let mut input = BufReader::new(answer.as_bytes());
+            match receive_answer(&mut input, &mut partial, Some(&mut lines)) {
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof => (),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+    }
 
-        Ok(())
+    #[test]
+    fn data_block() {
+        assert_eq!(".\r\n", encode_data_block(&[]));
+        assert_eq!(
+            "one\r\n..\r\n..two\r\n\r\n...\r\nthree.\r\n.\r\n",
+            encode_data_block(&["one", ".", ".two", "", "..", "three."])
+        );
     }
 
     #[test]
-    fn parse_synthesis_voices() -> ClientResult<()> {
-        let lines = ["en", "afrikaans\taf", "lancashire\ten\tuk-north"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
-        let voices = super::parse_typed_lines::<SynthesisVoice>(&lines)?;
-        assert_eq!(3, voices.len());
-        assert_eq!("en", voices[0].name.as_str());
-        assert_eq!(Some(String::from("af")), voices[1].language);
-        assert_eq!(Some(String::from("uk-north")), voices[2].dialect);
+    fn write_text() -> ClientResult<()> {
+        let mut output = Vec::new();
+        write_text_bytes(&mut output, b"one\r\n.two\nthree")?;
+        assert_eq!(
+            b"one\r\n..two\r\nthree\r\n.\r\n".as_slice(),
+            output.as_slice()
+        );
+
+        let mut output = Vec::new();
+        assert!(matches!(
+            write_text_bytes(&mut output, b"\xff\xfe"),
+            Err(ClientError::Io(_))
+        ));
+        assert!(output.is_empty());
         Ok(())
     }
 }
@@ -0,0 +1,71 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2021-2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::time::Duration;
+
+/// How failed requests are sent again, see [`crate::Client::retry`].
+///
+/// The delay doubles after each attempt up to a maximum. Only errors for which
+/// [`crate::ClientError::is_retryable`] is true are retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+
+    /// Delay before the given retry, starting at 0.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .checked_mul(2u32.saturating_pow(retry))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
+/// Three retries starting after 10 ms, with at most 1 s between attempts.
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn exponential_delay() {
+        let policy = RetryPolicy::default();
+        assert_eq!(Duration::from_millis(10), policy.delay(0));
+        assert_eq!(Duration::from_millis(40), policy.delay(2));
+        assert_eq!(Duration::from_secs(1), policy.delay(10));
+        assert_eq!(Duration::from_secs(1), policy.delay(u32::MAX));
+        assert_eq!(Duration::ZERO, RetryPolicy::none().delay(1));
+    }
+}
@@ -0,0 +1,63 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2021-2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::client::{Client, Source};
+use crate::types::*;
+
+/// Client that can be cloned and shared between threads.
+///
+/// All the handles use the same connection. Each call holds the lock for a whole
+/// exchange, so a request and its response are never interleaved with another thread.
+///
+/// Beware that the lock is held while waiting for the response. A thread blocked on
+/// [`Client::receive_event`] prevents the other threads from sending commands until an
+/// event arrives. Use [`Client::split`] when events must be read continuously.
+pub struct SharedClient<S: Read + Write + Source> {
+    client: Arc<Mutex<Client<S>>>,
+}
+
+impl<S: Read + Write + Source> SharedClient<S> {
+    /// Share an existing client.
+    pub fn new(client: Client<S>) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    /// Lock the client for the duration of the guard.
+    pub fn lock(&self) -> ClientResult<MutexGuard<'_, Client<S>>> {
+        self.client
+            .lock()
+            .map_err(|_| ClientError::io_error(io::ErrorKind::Other, "client lock poisoned"))
+    }
+
+    /// Run a sequence of calls on the client while holding the lock.
+    pub fn with<T, F>(&self, f: F) -> ClientResult<T>
+    where
+        F: FnOnce(&mut Client<S>) -> ClientResult<T>,
+    {
+        f(&mut *self.lock()?)
+    }
+
+    /// Send a request and receive its response while holding the lock.
+    pub fn request(&self, request: Request) -> ClientResult<Response> {
+        self.with(|client| client.send(request)?.receive())
+    }
+}
+
+impl<S: Read + Write + Source> Clone for SharedClient<S> {
+    fn clone(&self) -> Self {
+        Self {
+            client: Arc::clone(&self.client),
+        }
+    }
+}
@@ -0,0 +1,380 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2021-2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+#[cfg(feature = "normalize")]
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::client::{Client, Source};
+use crate::types::*;
+
+/// Append text to a SSML document, escaping the markup characters.
+fn push_escaped(ssml: &mut String, text: &str) {
+    for ch in text.chars() {
+        match ch {
+            '&' => ssml.push_str("&amp;"),
+            '<' => ssml.push_str("&lt;"),
+            '>' => ssml.push_str("&gt;"),
+            '"' => ssml.push_str("&quot;"),
+            '\'' => ssml.push_str("&apos;"),
+            ch => ssml.push(ch),
+        }
+    }
+}
+
+/// How the text passed to [`Client::say_line`] is escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapePolicy {
+    /// Text sent verbatim: lines starting with a dot are not doubled, so a line made of a
+    /// single dot is refused.
+    None,
+    /// Markup characters replaced by XML entities, then lines escaped as [`EscapePolicy::SsipDots`].
+    XmlEntities,
+    /// Lines starting with a dot escaped with a second dot, as required in plain mode.
+    SsipDots,
+}
+
+impl EscapePolicy {
+    /// Policy used when none is set: XML entities in SSML mode, dots otherwise.
+    pub fn for_ssml_mode(ssml_mode: bool) -> Self {
+        if ssml_mode {
+            EscapePolicy::XmlEntities
+        } else {
+            EscapePolicy::SsipDots
+        }
+    }
+}
+
+/// What [`TextNormalizer`] does with control characters other than line feeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlChars {
+    /// Control characters sent unchanged.
+    #[default]
+    Keep,
+    /// Control characters removed.
+    Strip,
+    /// Control characters and whitespaces other than a space replaced by a space.
+    Replace,
+}
+
+/// Transformation applied to the text before it's escaped.
+///
+/// The default normalizer leaves the text unchanged. Call [`TextNormalizer::normalize`] to
+/// preview the text that would be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextNormalizer {
+    /// Compose the characters in Unicode normalization form C.
+    #[cfg(feature = "normalize")]
+    pub nfc: bool,
+    /// Handling of control characters.
+    pub control_chars: ControlChars,
+}
+
+impl TextNormalizer {
+    /// Normalizer that does nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compose the characters in Unicode normalization form C.
+    ///
+    /// Only available with the `normalize` feature, that depends on `unicode-normalization`.
+    #[cfg(feature = "normalize")]
+    pub fn nfc(mut self, nfc: bool) -> Self {
+        self.nfc = nfc;
+        self
+    }
+
+    /// Set how control characters are handled.
+    pub fn control_chars(mut self, control_chars: ControlChars) -> Self {
+        self.control_chars = control_chars;
+        self
+    }
+
+    /// Whether the text is always left unchanged.
+    pub fn is_identity(&self) -> bool {
+        #[cfg(feature = "normalize")]
+        if self.nfc {
+            return false;
+        }
+        self.control_chars == ControlChars::Keep
+    }
+
+    /// Return the normalized text, borrowed if nothing changed.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        #[cfg(feature = "normalize")]
+        if self.nfc && is_nfc_quick(text.chars()) != IsNormalized::Yes {
+            text = Cow::Owned(text.nfc().collect());
+        }
+        let is_line_feed = |ch: char| ch == '\n';
+        match self.control_chars {
+            ControlChars::Keep => (),
+            ControlChars::Strip => {
+                if text.chars().any(|ch| ch.is_control() && !is_line_feed(ch)) {
+                    text = Cow::Owned(
+                        text.chars()
+                            .filter(|&ch| !ch.is_control() || is_line_feed(ch))
+                            .collect(),
+                    );
+                }
+            }
+            ControlChars::Replace => {
+                let replaced =
+                    |ch: char| (ch.is_control() || ch.is_whitespace()) && !matches!(ch, ' ' | '\n');
+                if text.chars().any(replaced) {
+                    text = Cow::Owned(
+                        text.chars()
+                            .map(|ch| if replaced(ch) { ' ' } else { ch })
+                            .collect(),
+                    );
+                }
+            }
+        }
+        text
+    }
+}
+
+/// Default limit of [`Client::max_message_bytes`].
+///
+/// Output modules are known to truncate messages of a few kilobytes.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 4096;
+
+/// Split a text in chunks of at most `max` bytes.
+///
+/// Chunks end at sentence boundaries, or at word boundaries for sentences too long.
+/// Words too long are split between characters.
+pub(crate) fn split_chunks(text: &str, max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    push_chunks(
+        text.trim(),
+        max,
+        &[Boundary::Sentence, Boundary::Word],
+        &mut chunks,
+    );
+    chunks
+}
+
+fn push_chunks<'a>(text: &'a str, max: usize, boundaries: &[Boundary], chunks: &mut Vec<&'a str>) {
+    if text.len() <= max {
+        if !text.is_empty() {
+            chunks.push(text);
+        }
+        return;
+    }
+    let Some((boundary, finer)) = boundaries.split_first() else {
+        let mut start = 0;
+        while start < text.len() {
+            let mut end = (start + max).min(text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end == start {
+                end += text[start..].chars().next().map_or(0, char::len_utf8);
+            }
+            chunks.push(&text[start..end]);
+            start = end;
+        }
+        return;
+    };
+    let mut offsets = boundary_offsets(text, *boundary);
+    offsets.push(text.len());
+    let (mut start, mut end) = (0, 0);
+    for next in offsets.into_iter().skip(1) {
+        if text[start..next].trim_end().len() > max && end > start {
+            push_chunks(text[start..end].trim_end(), max, finer, chunks);
+            start = end;
+        }
+        end = next;
+    }
+    push_chunks(text[start..].trim_end(), max, finer, chunks);
+}
+
+/// Replace the markup characters by XML entities.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    push_escaped(&mut escaped, text);
+    escaped
+}
+
+/// Unit of text delimited by index marks, see [`Client::say_with_boundaries`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Sequence of non-whitespace characters
+    Word,
+    /// Text ending with `.`, `!` or `?` followed by a whitespace
+    Sentence,
+}
+
+/// Byte offsets where each word or sentence of the text starts.
+pub(crate) fn boundary_offsets(text: &str, boundary: Boundary) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut at_boundary = true;
+    let mut previous = ' ';
+    for (offset, ch) in text.char_indices() {
+        if ch.is_whitespace() {
+            at_boundary |= match boundary {
+                Boundary::Word => true,
+                Boundary::Sentence => matches!(previous, '.' | '!' | '?'),
+            };
+        } else if at_boundary {
+            offsets.push(offset);
+            at_boundary = false;
+        }
+        previous = ch;
+    }
+    offsets
+}
+
+/// Builder of a text interleaved with index marks.
+///
+/// The text is rendered as SSML with `<mark>` tags. When the message is spoken, the server
+/// sends an [`EventType::IndexMark`] event with the name of each mark reached.
+///
+/// ```no_run
+/// use ssip_client_async::{fifo, ClientName, SpeechBuilder};
+/// let mut client = fifo::Builder::new().build()?;
+/// client
+///     .set_client_name(ClientName::new("joe", "hello"))?
+///     .check_client_name_set()?;
+/// let (id, marks) = SpeechBuilder::new()
+///     .text("Hello")
+///     .mark("world")
+///     .text(" world")
+///     .speak(&mut client)?;
+/// # Ok::<(), ssip_client_async::ClientError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SpeechBuilder {
+    body: String,
+    marks: Vec<String>,
+}
+
+impl SpeechBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append plain text, it may contain several lines.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        push_escaped(&mut self.body, text);
+        self
+    }
+
+    /// Append an index mark.
+    pub fn mark(&mut self, name: &str) -> &mut Self {
+        self.body.push_str("<mark name=\"");
+        push_escaped(&mut self.body, name);
+        self.body.push_str("\"/>");
+        self.marks.push(name.to_string());
+        self
+    }
+
+    /// Names of the marks in the order they were appended.
+    pub fn marks(&self) -> &[String] {
+        &self.marks
+    }
+
+    /// Render the SSML document.
+    pub fn to_ssml(&self) -> String {
+        format!("<speak>{}</speak>", self.body)
+    }
+
+    /// Speak the text in SSML mode and return the message id and the names of the marks.
+    pub fn speak<S: Read + Write + Source>(
+        &self,
+        client: &mut Client<S>,
+    ) -> ClientResult<(MessageId, Vec<String>)> {
+        let id = client.say_ssml(&self.to_ssml())?;
+        Ok((id, self.marks.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        boundary_offsets, escape_xml, split_chunks, Boundary, ControlChars, EscapePolicy,
+        SpeechBuilder, TextNormalizer,
+    };
+    use std::borrow::Cow;
+
+    #[test]
+    fn render_marks() {
+        let mut builder = SpeechBuilder::new();
+        builder
+            .text("Tom & Jerry")
+            .mark("m1")
+            .text("\n<end>")
+            .mark("\"m2\"");
+        assert_eq!(
+            "<speak>Tom &amp; Jerry<mark name=\"m1\"/>\n&lt;end&gt;<mark name=\"&quot;m2&quot;\"/></speak>",
+            builder.to_ssml()
+        );
+        assert_eq!(&["m1", "\"m2\""], builder.marks());
+    }
+
+    #[test]
+    fn escape_policy() {
+        assert_eq!("a &lt;b&gt; &amp; c", escape_xml("a <b> & c"));
+        assert_eq!(EscapePolicy::XmlEntities, EscapePolicy::for_ssml_mode(true));
+        assert_eq!(EscapePolicy::SsipDots, EscapePolicy::for_ssml_mode(false));
+    }
+
+    #[test]
+    fn find_boundaries() {
+        let text = " Hi there. Mr. Smith is\nhere!  Bye";
+        assert_eq!(
+            vec![1, 4, 11, 15, 21, 24, 31],
+            boundary_offsets(text, Boundary::Word)
+        );
+        assert_eq!(
+            vec![1, 11, 15, 31],
+            boundary_offsets(text, Boundary::Sentence)
+        );
+        assert!(boundary_offsets("  ", Boundary::Word).is_empty());
+    }
+
+    #[test]
+    fn split_in_chunks() {
+        let text = " One. Two words. A much longer sentence here! End";
+        assert_eq!(vec![text.trim()], split_chunks(text, 100));
+        assert_eq!(
+            vec!["One. Two words.", "A much longer", "sentence here!", "End"],
+            split_chunks(text, 15)
+        );
+        assert_eq!(vec!["éé", "é"], split_chunks("ééé", 5));
+        assert!(split_chunks(" ", 10).is_empty());
+    }
+
+    #[test]
+    fn normalize_text() {
+        let text = "Cafe\u{301}\u{7}\tok\u{a0}!\n";
+        assert!(matches!(
+            TextNormalizer::new().normalize(text),
+            Cow::Borrowed(_)
+        ));
+        let strip = TextNormalizer::new().control_chars(ControlChars::Strip);
+        assert_eq!("Cafe\u{301}ok\u{a0}!\n", strip.normalize(text));
+    }
+
+    #[test]
+    #[cfg(feature = "normalize")]
+    fn normalize_nfc() {
+        let text = "Cafe\u{301}\u{7}\tok\u{a0}!\n";
+        let nfc = TextNormalizer::new().nfc(true);
+        assert_eq!("Café\u{7}\tok\u{a0}!\n", nfc.normalize(text));
+        assert!(matches!(nfc.normalize("Café"), Cow::Borrowed(_)));
+        let strip = nfc.control_chars(ControlChars::Strip);
+        assert_eq!("Caféok\u{a0}!\n", strip.normalize(text));
+        let replace = nfc.control_chars(ControlChars::Replace);
+        assert_eq!("Café  ok !\n", replace.normalize(text));
+    }
+}
@@ -17,6 +17,7 @@ mod synchronous {
 
     use crate::client::Client;
     use crate::net::StreamMode;
+    use crate::speech::{EscapePolicy, TextNormalizer};
 
     struct Addresses(Vec<SocketAddr>);
 
@@ -30,6 +31,9 @@ mod synchronous {
     pub struct Builder {
         addrs: Addresses,
         mode: StreamMode,
+        escape_policy: Option<EscapePolicy>,
+        chunking: bool,
+        normalizer: TextNormalizer,
     }
 
     impl Builder {
@@ -37,6 +41,9 @@ mod synchronous {
             Ok(Self {
                 addrs: Addresses(addrs.to_socket_addrs()?.collect::<Vec<SocketAddr>>()),
                 mode: StreamMode::Blocking,
+                escape_policy: None,
+                chunking: false,
+                normalizer: TextNormalizer::default(),
             })
         }
 
@@ -50,6 +57,24 @@ mod synchronous {
             self
         }
 
+        /// Escape policy of the client, see [`Client::set_escape_policy`].
+        pub fn escape_policy(&mut self, policy: EscapePolicy) -> &mut Self {
+            self.escape_policy = Some(policy);
+            self
+        }
+
+        /// Split long texts in several messages, see [`Client::set_chunking`].
+        pub fn chunking(&mut self, chunking: bool) -> &mut Self {
+            self.chunking = chunking;
+            self
+        }
+
+        /// Transform the texts before sending them, see [`Client::set_normalizer`].
+        pub fn normalizer(&mut self, normalizer: TextNormalizer) -> &mut Self {
+            self.normalizer = normalizer;
+            self
+        }
+
         pub fn build(&self) -> io::Result<Client<TcpStream>> {
             let input = TcpStream::connect(&self.addrs)?;
             match self.mode {
@@ -58,7 +83,12 @@ mod synchronous {
                 StreamMode::TimeOut(timeout) => input.set_read_timeout(Some(timeout))?,
             }
             let output = input.try_clone()?;
-            Ok(Client::new(BufReader::new(input), BufWriter::new(output)))
+            let mut client = Client::new(BufReader::new(input), BufWriter::new(output));
+            client
+                .set_escape_policy(self.escape_policy)
+                .set_chunking(self.chunking)
+                .set_normalizer(self.normalizer);
+            Ok(client)
         }
     }
 }
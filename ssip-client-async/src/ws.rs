@@ -0,0 +1,113 @@
+// ssip-client -- Speech Dispatcher client in Rust
+// Copyright (c) 2021-2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! SSIP over a WebSocket, for example through a relay.
+//!
+//! Each protocol line is sent in its own text frame without the CRLF terminator. Lines are
+//! not buffered into bigger frames, so a relay can forward frames to Speech Dispatcher as
+//! lines. Received frames may also hold several lines separated by CRLF.
+
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpStream;
+
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::client::{read_response, write_request};
+use crate::protocol::PartialAnswer;
+use crate::types::*;
+
+/// Convert a WebSocket error into a client error.
+fn ws_error(err: tungstenite::Error) -> ClientError {
+    match err {
+        tungstenite::Error::Io(err) => ClientError::from(err),
+        err => ClientError::io_error(io::ErrorKind::Other, &err.to_string()),
+    }
+}
+
+/// Stream of the lines received in text frames.
+struct FrameReader<S> {
+    socket: WebSocket<S>,
+    pending: VecDeque<u8>,
+}
+
+impl<S: Read + Write> Read for FrameReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => {
+                    self.pending.extend(text.as_bytes());
+                    if !text.ends_with('\n') {
+                        self.pending.extend(b"\r\n");
+                    }
+                }
+                Ok(Message::Close(_))
+                | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Ok(0)
+                }
+                Ok(_) => (),
+                Err(tungstenite::Error::Io(err)) => return Err(err),
+                Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+            }
+        }
+        self.pending.read(buf)
+    }
+}
+
+/// SSIP client on a WebSocket
+///
+/// Requests and responses are the same as with [`crate::Client`], only the transport differs.
+/// `QUIT` is not sent on drop, call [`WsClient::close`] to end the session.
+pub struct WsClient<S: Read + Write> {
+    input: BufReader<FrameReader<S>>,
+    partial: PartialAnswer,
+}
+
+impl<S: Read + Write> WsClient<S> {
+    /// Create a client on a WebSocket whose handshake is done.
+    pub fn new(socket: WebSocket<S>) -> Self {
+        Self {
+            input: BufReader::new(FrameReader {
+                socket,
+                pending: VecDeque::new(),
+            }),
+            partial: PartialAnswer::default(),
+        }
+    }
+
+    /// Send a request, one frame per line.
+    pub fn send(&mut self, request: Request) -> ClientResult<&mut Self> {
+        let mut bytes = Vec::new();
+        write_request(&mut bytes, request)?;
+        let text =
+            String::from_utf8(bytes).map_err(|_| ClientError::invalid_data("invalid UTF-8"))?;
+        let socket = &mut self.input.get_mut().socket;
+        for line in text.split_terminator("\r\n") {
+            socket.send(Message::text(line)).map_err(ws_error)?;
+        }
+        Ok(self)
+    }
+
+    /// Receive one response.
+    pub fn receive(&mut self) -> ClientResult<Response> {
+        read_response(&mut self.input, &mut self.partial)
+    }
+
+    /// Send `QUIT` and close the WebSocket.
+    pub fn close(&mut self) -> ClientResult<()> {
+        self.send(Request::Quit)?;
+        self.input.get_mut().socket.close(None).map_err(ws_error)
+    }
+}
+
+/// Connect to a `ws://` URL.
+pub fn connect(url: &str) -> ClientResult<WsClient<MaybeTlsStream<TcpStream>>> {
+    let (socket, _) = tungstenite::connect(url).map_err(ws_error)?;
+    Ok(WsClient::new(socket))
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(all(unix, feature = "async-io"))]
+
+use ssip_client_async::{fifo::asynchronous_async_io::Builder, *};
+
+#[allow(dead_code)]
+mod server;
+
+#[test]
+fn say_hello() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 5] = [
+        (
+            "SET self CLIENT_NAME test:test:main\r\n",
+            "208-7\r\n208 OK CLIENT NAME SET\r\n",
+        ),
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+        ("SET self RATE 20\r\n", "203 OK RATE SET\r\n"),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("async_io.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    smol::block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client
+            .set_client_name(ClientName::new("test", "test"))
+            .await?
+            .check_client_name_set()
+            .await?;
+        assert_eq!(Some(&7), client.client_id());
+        client.speak().await?.check_receiving_data().await?;
+        client.send_lines(&[String::from("Hello")]).await?;
+        assert_eq!(21, client.receive_message_id().await?);
+        client
+            .set_rate(ClientScope::Current, Rate::try_from(20).unwrap())
+            .await?
+            .check_status(OK_RATE_SET)
+            .await?;
+        AsyncSsipClient::quit(&mut client).await?;
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn send_escaped_lines() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        (
+            "SET self CLIENT_NAME test:test:main\r\n",
+            "208 OK CLIENT NAME SET\r\n",
+        ),
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        ("..x\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("escape.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    smol::block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client
+            .set_client_name(ClientName::new("test", "test"))
+            .await?
+            .check_client_name_set()
+            .await?;
+        client.speak().await?.check_receiving_data().await?;
+        client.send_lines(&[String::from(".x")]).await?;
+        assert_eq!(21, client.receive_message_id().await?);
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn wait_keepalive() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 2] = [
+        ("GET RATE\r\n", "251-10\r\n251 OK GET RETURNED\r\n"),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("wait_keepalive.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    smol::block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client.set_keepalive(Some(std::time::Duration::from_millis(10)));
+        client.wait_keepalive().await?;
+        client.quit().await?.check_status(OK_BYE).await?;
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
@@ -0,0 +1,49 @@
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(all(unix, feature = "async-std"))]
+
+use ::async_std::{
+    io::{BufReader, BufWriter},
+    os::unix::net::UnixStream,
+};
+use ssip_client_async::{async_std::AsyncClient, *};
+
+#[allow(dead_code)]
+mod server;
+
+#[test]
+fn send_escaped_lines() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        (
+            "SET self CLIENT_NAME test:test:main\r\n",
+            "208 OK CLIENT NAME SET\r\n",
+        ),
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        ("..x\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("escape.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    ::async_std::task::block_on(async {
+        let stream = UnixStream::connect(&socket_path).await?;
+        let mut client = AsyncClient::new(BufReader::new(stream.clone()), BufWriter::new(stream));
+        client
+            .set_client_name(ClientName::new("test", "test"))
+            .await?
+            .check_client_name_set()
+            .await?;
+        client.speak().await?.check_receiving_data().await?;
+        client.send_lines(&[String::from(".x")]).await?;
+        assert_eq!(21, client.receive_message_id().await?);
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
@@ -7,7 +7,7 @@
 // modified, or distributed except according to those terms.
 
 #[cfg(feature = "async-mio")]
-use mio::{Events, Poll, Token};
+use mio::{Events, Interest, Poll, Token};
 #[cfg(feature = "async-mio")]
 use std::{
     io::{Read, Write},
@@ -16,7 +16,7 @@ use std::{
 };
 
 #[cfg(feature = "async-mio")]
-use ssip_client::{client::Source, *};
+use ssip_client_async::{client::Source, *};
 
 #[cfg(feature = "async-mio")]
 mod server;
@@ -68,20 +68,24 @@ impl<'a, 'b> State<'a, 'b> {
     }
 }
 
+#[cfg(feature = "async-mio")]
+const INPUT_TOKEN: Token = Token(0);
+#[cfg(feature = "async-mio")]
+const OUTPUT_TOKEN: Token = Token(1);
+
 #[cfg(feature = "async-mio")]
 fn basic_async_client_communication<S: Read + Write + Source>(
     client: &mut QueuedClient<S>,
+    poll: &mut Poll,
 ) -> ClientResult<usize> {
     let get_requests = vec![Request::GetOutputModule, Request::GetRate];
     let get_answers = vec!["espeak", "10"];
     let mut state = State::new(get_requests.iter(), get_answers.iter());
 
-    let mut poll = Poll::new()?;
     let mut events = Events::with_capacity(128);
-    let input_token = Token(0);
-    let output_token = Token(1);
+    let input_token = INPUT_TOKEN;
+    let output_token = OUTPUT_TOKEN;
     let timeout = Duration::new(0, 500 * 1000 * 1000 /* 500 ms */);
-    client.register(&poll, input_token, output_token).unwrap();
     client.push(Request::SetName(ClientName::new("test", "test")));
     while !state.terminated() {
         if !state.writable || !client.has_next() {
@@ -143,7 +147,9 @@ fn basic_async_unix_communication() -> ClientResult<()> {
     assert!(!socket_path.exists());
     let handle = server::run_unix(&socket_path, &BASIC_COMMUNICATION)?;
     let mut client = QueuedClient::new(fifo::Builder::new().path(&socket_path).build()?);
-    let countdown = basic_async_client_communication(&mut client)?;
+    let mut poll = Poll::new()?;
+    client.register(&poll, INPUT_TOKEN, OUTPUT_TOKEN)?;
+    let countdown = basic_async_client_communication(&mut client, &mut poll)?;
     handle.join().unwrap().unwrap();
     socket_dir.close()?;
     assert!(countdown > 0);
@@ -156,8 +162,65 @@ fn basic_async_tcp_communication() -> ClientResult<()> {
     let addr = "127.0.0.1:9999";
     let handle = server::run_tcp(addr, &BASIC_COMMUNICATION)?;
     let mut client = QueuedClient::new(tcp::Builder::new(addr.parse().unwrap()).build()?);
-    let countdown = basic_async_client_communication(&mut client)?;
+    let mut poll = Poll::new()?;
+    client.register(&poll, INPUT_TOKEN, OUTPUT_TOKEN)?;
+    let countdown = basic_async_client_communication(&mut client, &mut poll)?;
+    handle.join().unwrap().unwrap();
+    assert!(countdown > 0);
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, feature = "async-mio"))]
+fn reregister_async_unix_communication() -> ClientResult<()> {
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("reregister.socket");
+    let handle = server::run_unix(&socket_path, &BASIC_COMMUNICATION)?;
+    let mut client = QueuedClient::new(fifo::Builder::new().path(&socket_path).build()?);
+    let mut poll = Poll::new()?;
+    client.register_with(poll.registry(), Token(10), Token(11))?;
+    // Events with the old tokens would be ignored and the countdown would expire.
+    client.reregister(&poll, INPUT_TOKEN, OUTPUT_TOKEN)?;
+    let countdown = basic_async_client_communication(&mut client, &mut poll)?;
+    client.deregister(&poll)?;
     handle.join().unwrap().unwrap();
+    socket_dir.close()?;
     assert!(countdown > 0);
     Ok(())
 }
+
+#[test]
+#[cfg(all(unix, feature = "async-mio"))]
+fn register_read_interest() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 1] = [(
+        "SET self CLIENT_NAME test:test:main\r\n",
+        "208 OK CLIENT NAME SET\r\n",
+    )];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("read_interest.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = QueuedClient::new(fifo::Builder::new().path(&socket_path).build()?);
+    let mut poll = Poll::new()?;
+    let mut events = Events::with_capacity(16);
+    let token = Token(5);
+    client.register_interest(&poll, token, Interest::READABLE)?;
+    client.push(Request::SetName(ClientName::new("test", "test")));
+    assert!(client.send_next()?);
+    let mut response = None;
+    for _ in 0..10 {
+        poll.poll(&mut events, Some(Duration::from_millis(500)))?;
+        for event in &events {
+            assert_eq!(token, event.token());
+            assert!(!event.is_writable());
+            response = Some(client.receive_next()?);
+        }
+        if response.is_some() {
+            break;
+        }
+    }
+    assert_eq!(Some(Response::ClientNameSet), response);
+    client.deregister(&poll)?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
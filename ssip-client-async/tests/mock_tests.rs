@@ -0,0 +1,88 @@
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(feature = "mock")]
+
+use ssip_client_async::{mock::MockClient, *};
+
+#[test]
+fn record_commands() -> ClientResult<()> {
+    let mut client = MockClient::new();
+    smol::block_on(async {
+        assert_eq!(1, client.say_line("Hello").await?);
+        client
+            .set_rate(ClientScope::Current, Rate::try_from(20).unwrap())
+            .await?;
+        assert_eq!(2, client.say_line("World").await?);
+        client.stop(MessageScope::All).await
+    })?;
+    assert_eq!(
+        vec![
+            Request::Speak,
+            Request::SendLine(String::from("Hello")),
+            Request::SetRate(ClientScope::Current, Rate::try_from(20).unwrap()),
+            Request::Speak,
+            Request::SendLine(String::from("World")),
+            Request::Stop(MessageScope::All),
+        ],
+        client.take_requests()
+    );
+    assert!(client.requests().is_empty());
+    Ok(())
+}
+
+#[test]
+fn scripted_results_and_events() {
+    let mut client = MockClient::new();
+    client
+        .push_result(Err(ClientError::UnexpectedStatus(OK_STOPPED)))
+        .push_event(Event::new(EventType::Begin, "21", "7"));
+    smol::block_on(async {
+        assert!(matches!(
+            client.say_line("Hello").await,
+            Err(ClientError::UnexpectedStatus(OK_STOPPED))
+        ));
+        assert!(client.cancel(MessageScope::Last).await.is_ok());
+        assert_eq!(
+            Event::new(EventType::Begin, "21", "7"),
+            client.receive_event().await.unwrap()
+        );
+        assert!(matches!(
+            client.receive_event().await,
+            Err(ClientError::Disconnected)
+        ));
+    });
+    assert_eq!(3, client.requests().len());
+}
+
+/// Generic code running on the synchronous client or on the mock.
+fn say_and_stop<C: client::SsipClient>(client: &mut C) -> ClientResult<MessageId> {
+    let id = client.say_line("Hello")?;
+    client.stop(MessageScope::Message(id))?;
+    Ok(id)
+}
+
+#[test]
+fn synchronous_commands() -> ClientResult<()> {
+    let mut client = MockClient::new();
+    assert_eq!(1, say_and_stop(&mut client)?);
+    assert_eq!(
+        vec![
+            Request::Speak,
+            Request::SendLine(String::from("Hello")),
+            Request::Stop(MessageScope::Message(1)),
+        ],
+        client.take_requests()
+    );
+    client.push_result(Err(ClientError::UnexpectedStatus(OK_STOPPED)));
+    assert!(matches!(
+        say_and_stop(&mut client),
+        Err(ClientError::UnexpectedStatus(OK_STOPPED))
+    ));
+    Ok(())
+}
@@ -12,12 +12,14 @@ use ssip_client_async::{client::Source, *};
 use std::os::unix::net::UnixStream;
 #[cfg(not(feature = "async-mio"))]
 use std::{
-    io::{self, Read, Write},
+    io::{self, BufRead, Read, Write},
     net::TcpStream,
+    ops::ControlFlow,
     sync::{
         atomic::{AtomicU16, Ordering as AtomicOrdering},
         Arc,
     },
+    time::Duration,
 };
 
 #[cfg(not(feature = "async-mio"))]
@@ -117,31 +119,1805 @@ fn connect_and_quit() -> ClientResult<()> {
     Ok(())
 }
 
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn underlying_stream() -> ClientResult<()> {
+    test_client!(&[SET_CLIENT_COMMUNICATION], |client| {
+        assert!(client.get_ref().peer_addr().is_ok());
+        client
+            .get_mut()
+            .set_read_timeout(Some(Duration::from_secs(1)))?;
+        assert_eq!(
+            Some(Duration::from_secs(1)),
+            client.get_ref().read_timeout()?
+        );
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn set_invalid_client_name() -> ClientResult<()> {
+    test_client!(&[SET_CLIENT_COMMUNICATION], |client| {
+        match client.set_client_name(ClientName::new("test", "test:injected")) {
+            Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidInput => Ok(()),
+            _ => panic!("expecting error 'invalid input'"),
+        }
+    })
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn client_id_from_client_name_reply() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self CLIENT_NAME test:test:main\r\n",
+                "208-42\r\n208 OK CLIENT NAME SET\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(None, client.client_id());
+            client
+                .set_client_name(ClientName::new("test", "test"))
+                .unwrap()
+                .check_client_name_set()
+                .unwrap();
+            assert_eq!(Some(&42), client.client_id());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn handshake() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self CLIENT_NAME test:test:main\r\n",
+                "208 OK CLIENT NAME SET 17\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(
+                Some(&Handshake {
+                    code: OK_CLIENT_NAME_SET,
+                    message: String::from("CLIENT NAME SET"),
+                    client_id: None,
+                }),
+                client.handshake()
+            );
+            client
+                .set_client_name(ClientName::new("test", "test"))
+                .unwrap()
+                .check_client_name_set()
+                .unwrap();
+            assert_eq!(
+                Some(&Handshake {
+                    code: OK_CLIENT_NAME_SET,
+                    message: String::from("CLIENT NAME SET 17"),
+                    client_id: Some(17),
+                }),
+                client.handshake()
+            );
+            assert_eq!(Some(&17), client.client_id());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn get_client_id_from_handshake() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self CLIENT_NAME test:test:main\r\n",
+                "208-42\r\n208 OK CLIENT NAME SET\r\n",
+            ),
+        ],
+        |client| {
+            client
+                .set_client_name(ClientName::new("test", "test"))
+                .unwrap()
+                .check_client_name_set()
+                .unwrap();
+            assert_eq!(42, client.get_client_id().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn get_client_id_from_query() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "HISTORY GET CLIENT_ID\r\n",
+                "245-7\r\n245 OK CLIENT ID SENT\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(7, client.get_client_id().unwrap());
+            assert_eq!(Some(&7), client.client_id());
+            // The id is cached, no further query is sent.
+            assert_eq!(7, client.get_client_id().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn quit_on_drop() -> ClientResult<()> {
+    // The client doesn't wait for the answer, the socket may already be closed.
+    const COMMUNICATION: [(&str, &str); 2] = [SET_CLIENT_COMMUNICATION, ("QUIT\r\n", "")];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    {
+        let mut client = ssip_client_async::fifo::Builder::new()
+            .path(&socket_path)
+            .build()?;
+        client
+            .set_client_name(ClientName::new("test", "test"))?
+            .check_client_name_set()?;
+    }
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn into_inner() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 2] = [
+        SET_CLIENT_COMMUNICATION,
+        ("STOP self\r\n", "210 OK STOPPED\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    // No QUIT is sent, the server would fail reading it instead of STOP.
+    let mut stream = client.into_inner()?;
+    stream.write_all(b"STOP self\r\n")?;
+    let mut answer = String::new();
+    io::BufReader::new(stream).read_line(&mut answer)?;
+    assert_eq!("210 OK STOPPED\r\n", answer);
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn queued_client_receive_all() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        (
+            "STOP all\r\nCANCEL self\r\n",
+            "210 OK STOPPED\r\n703-21\r\n703-test\r\n703 CANCELED\r\n213 OK CANCELED\r\n",
+        ),
+        ("QUIT\r\n", ""),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    client.get_ref().set_nonblocking(true)?;
+    let mut client = QueuedClient::new(client);
+    assert!(client.receive_all()?.is_empty());
+    assert_eq!(None, client.peek_next());
+    client.push(Request::Speak);
+    assert_eq!(1, client.clear());
+    client.push(Request::Speak);
+    client.push(Request::SendLine(String::from("hello")));
+    assert_eq!(2, client.cancel_all());
+    client.push(Request::Cancel(MessageScope::Last));
+    assert_eq!(Some(&Request::Stop(MessageScope::All)), client.peek_next());
+    assert_eq!(Some(&Request::Cancel(MessageScope::Last)), client.last());
+    while client.send_next()? {}
+    let mut responses = Vec::new();
+    for _ in 0..100 {
+        responses.extend(client.receive_all()?);
+        if responses.len() == 3 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        vec![
+            Response::Stopped,
+            Response::EventCanceled(EventId::new("21", "test")),
+            Response::Canceled
+        ],
+        responses
+    );
+    drop(client);
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn queued_client_clear_in_data() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 5] = [
+        SET_CLIENT_COMMUNICATION,
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        (".\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        (
+            "hello\r\n.\r\nSTOP all\r\n",
+            "225-22\r\n225 OK MESSAGE QUEUED\r\n210 OK STOPPED\r\n",
+        ),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    let mut client = QueuedClient::new(client);
+    // The block is ended by an empty text.
+    client.push(Request::Speak);
+    client.send_next()?;
+    assert_eq!(0, client.clear());
+    assert_eq!(Some(&Request::SendLines(Vec::new())), client.peek_next());
+    while client.send_next()? {}
+    assert_eq!(Response::ReceivingData, client.receive_next()?);
+    assert_eq!(Response::MessageQueued, client.receive_next()?);
+    // The text already pushed is kept before STOP.
+    client.push(Request::Speak);
+    client.push(Request::SendLine(String::from("hello")));
+    client.push(Request::Cancel(MessageScope::Last));
+    client.send_next()?;
+    assert_eq!(1, client.cancel_all());
+    assert_eq!(
+        Some(&Request::SendLine(String::from("hello"))),
+        client.peek_next()
+    );
+    while client.send_next()? {}
+    assert_eq!(Response::ReceivingData, client.receive_next()?);
+    assert_eq!(Response::MessageQueued, client.receive_next()?);
+    assert_eq!(Response::Stopped, client.receive_next()?);
+    drop(client);
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn shared_client() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        ("STOP self\r\n", "210 OK STOPPED\r\n"),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    let shared = SharedClient::new(client);
+    let other = shared.clone();
+    std::thread::spawn(move || other.request(Request::Stop(MessageScope::Last)))
+        .join()
+        .unwrap()
+        .map(|response| assert_eq!(Response::Stopped, response))?;
+    assert_eq!(Response::Bye, shared.request(Request::Quit)?);
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn split_client() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        (
+            "STOP self\r\n",
+            "210 OK STOPPED\r\n701-21\r\n701-test\r\n701 BEGIN\r\n",
+        ),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    let (mut reader, mut writer) = client.split();
+    let listener = std::thread::spawn(move || -> ClientResult<Vec<Response>> {
+        let mut responses = Vec::new();
+        for _ in 0..3 {
+            responses.push(reader.receive()?);
+        }
+        Ok(responses)
+    });
+    writer.send(Request::Stop(MessageScope::Last))?;
+    drop(writer);
+    assert_eq!(
+        vec![
+            Response::Stopped,
+            Response::EventBegin(EventId::new("21", "test")),
+            Response::Bye
+        ],
+        listener.join().unwrap()?
+    );
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn split_keeps_queued_events() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        (
+            "STOP self\r\n",
+            "701-21\r\n701-test\r\n701 BEGIN\r\n210 OK STOPPED\r\n",
+        ),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?
+        .stop(MessageScope::Last)?
+        .check_status(OK_STOPPED)?;
+    let (mut reader, writer) = client.split();
+    assert_eq!(
+        Event::new(EventType::Begin, "21", "test"),
+        reader.receive_event()?
+    );
+    drop(writer);
+    assert_eq!(Response::Bye, reader.receive()?);
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn generic_commands() -> ClientResult<()> {
+    use ssip_client_async::client::SsipClient;
+    fn say_and_stop<C: SsipClient>(client: &mut C) -> ClientResult<MessageId> {
+        client.set_rate(ClientScope::Current, Rate::try_from(-50).unwrap())?;
+        let id = client.say_line("Hello")?;
+        client.stop(MessageScope::Message(id))?;
+        Ok(id)
+    }
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self RATE -50\r\n", "203 OK RATE SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("STOP 21\r\n", "210 OK STOPPED\r\n"),
+        ],
+        |client| {
+            assert_eq!(21, say_and_stop(client).unwrap());
+            Ok(())
+        }
+    )
+}
+
 #[test]
 #[cfg(not(feature = "async-mio"))]
 fn say_one_line() -> ClientResult<()> {
     test_client!(
         &[
             SET_CLIENT_COMMUNICATION,
-            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello, world\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(
+                21,
+                client
+                    .speak()
+                    .unwrap()
+                    .check_status(OK_RECEIVING_DATA)
+                    .unwrap()
+                    .send_line("Hello, world")
+                    .unwrap()
+                    .receive_message_id()
+                    .unwrap()
+            );
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_bytes() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello,\r\n..world\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n"
+            ),
+        ],
+        |client| {
+            assert_eq!(21, client.say_bytes(b"Hello,\n.world").unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_text() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("..world\r\n.\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+        ],
+        |client| {
+            assert_eq!(21, client.say_text("Hello").unwrap());
+            assert_eq!(22, client.say_text(String::from(".world")).unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn buffered_block() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("BLOCK BEGIN\r\n", "260 OK INSIDE BLOCK\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("world\r\n.\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("BLOCK END\r\n", "261 OK OUTSIDE BLOCK\r\n"),
+        ],
+        |client| {
+            // Responses are read once the whole block is sent.
+            client
+                .block_begin()
+                .unwrap()
+                .speak()
+                .unwrap()
+                .send_line("Hello")
+                .unwrap()
+                .speak()
+                .unwrap()
+                .send_line("world")
+                .unwrap()
+                .block_end()
+                .unwrap()
+                .check_status(OK_INSIDE_BLOCK)
+                .unwrap()
+                .check_receiving_data()
+                .unwrap();
+            assert_eq!(21, client.receive_message_id().unwrap());
+            client.check_receiving_data().unwrap();
+            assert_eq!(22, client.receive_message_id().unwrap());
+            client.check_status(OK_OUTSIDE_BLOCK).unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn send_escaped_lines() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("..x\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("..\r\n.\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+        ],
+        |client| {
+            client.speak().unwrap().check_receiving_data().unwrap();
+            client.send_lines(&[String::from(".x")]).unwrap();
+            assert_eq!(21, client.receive_message_id().unwrap());
+            client.speak().unwrap().check_receiving_data().unwrap();
+            client.send_line(".").unwrap();
+            assert_eq!(22, client.receive_message_id().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn pipeline_write_error() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self RATE 0\r\n", "203 OK RATE SET\r\n"),
+            ("GET VOLUME\r\n", "251-100\r\n251 OK GET RETURNED\r\n"),
+        ],
+        |client| {
+            assert!(matches!(
+                client
+                    .pipeline()
+                    .push(Request::SetRate(ClientScope::Current, Rate::default()))
+                    .push(Request::SetName(ClientName::new("a:b", "c")))
+                    .execute(),
+                Err(ClientError::Io(_))
+            ));
+            // The reply to the request written before the error has been read.
+            assert_eq!(
+                Response::Get(String::from("100")),
+                client.send(Request::GetVolume).unwrap().receive().unwrap()
+            );
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn pipeline() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self RATE 0\r\nSET self PITCH 0\r\nGET VOLUME\r\n",
+                "203 OK RATE SET\r\n701-21\r\n701-test\r\n701 BEGIN\r\n204 OK PITCH SET\r\n251-100\r\n251 OK GET RETURNED\r\n",
+            ),
+            (
+                "SET self RATE 0\r\nSET self PITCH 0\r\n",
+                "203 OK RATE SET\r\n409 ERR PITCH TOO HIGH\r\n",
+            ),
+        ],
+        |client| {
+            let responses = client
+                .pipeline()
+                .push(Request::SetRate(ClientScope::Current, Rate::default()))
+                .push(Request::SetPitch(ClientScope::Current, Pitch::default()))
+                .push(Request::GetVolume)
+                .execute()
+                .unwrap();
+            assert_eq!(
+                vec![
+                    Response::RateSet,
+                    Response::PitchSet,
+                    Response::Get(String::from("100"))
+                ],
+                responses
+            );
+            match client
+                .pipeline()
+                .push(Request::SetRate(ClientScope::Current, Rate::default()))
+                .push(Request::SetPitch(ClientScope::Current, Pitch::default()))
+                .execute()
+            {
+                Err(ClientError::PipelineFailed { index: 1, source }) => {
+                    assert!(matches!(*source, ClientError::Ssip(_)))
+                }
+                result => panic!("unexpected result {:?}", result),
+            }
+            assert_eq!(
+                vec![Event::begin("21", "test")],
+                client.drain_events()
+            );
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn spell() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self SPELLING on\r\n", "207 OK SPELLING SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Pelecq\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SET self SPELLING off\r\n", "207 OK SPELLING SET\r\n"),
+            ("SET self SPELLING on\r\n", "207 OK SPELLING SET\r\n"),
+            ("SPEAK\r\n", "401 ERR NOT ALLOWED\r\n"),
+            ("SET self SPELLING off\r\n", "207 OK SPELLING SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(21, client.spell("Pelecq").unwrap());
+            assert!(matches!(client.spell("Hoyem"), Err(ClientError::Ssip(_))));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_in_language() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("GET LANGUAGE\r\n", "251-en\r\n251 OK GET RETURNED\r\n"),
+            ("SET self LANGUAGE de\r\n", "201 OK LANGUAGE SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Guten Tag\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SET self LANGUAGE en\r\n", "201 OK LANGUAGE SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(21, client.say_in_language("de", "Guten Tag").unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn protocol_version() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "HELP\r\n",
+                "248-  SPEAK           -- say text\r\n248-  SSIP version 0.12.1\r\n248 OK HELP SENT\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!((0, 12), client.protocol_version().unwrap());
+            // The version is cached.
+            assert_eq!((0, 12), client.protocol_version().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn commands_on_clients() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("STOP 7\r\n", "210 OK STOPPED\r\n"),
+            ("CANCEL 7\r\n", "213 OK CANCELED\r\n"),
+            ("PAUSE self\r\n", "211 OK PAUSED\r\n"),
+            ("RESUME 8\r\n", "402 ERR NO SUCH CLIENT\r\n"),
+        ],
+        |client| {
+            client
+                .stop_client(ClientScope::Client(7))
+                .unwrap()
+                .check_status(OK_STOPPED)
+                .unwrap();
+            client
+                .cancel_client(ClientScope::Client(7))
+                .unwrap()
+                .check_status(OK_CANCELED)
+                .unwrap();
+            client
+                .pause_client(ClientScope::Current)
+                .unwrap()
+                .check_status(OK_PAUSED)
+                .unwrap();
+            assert!(matches!(
+                client
+                    .resume_client(ClientScope::Client(8))
+                    .unwrap()
+                    .check_status(OK_RESUMED),
+                Err(ClientError::UnknownId { id: 8, .. })
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn subscribe_events() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 5] = [
+        SET_CLIENT_COMMUNICATION,
+        (
+            "SET self NOTIFICATION all on\r\n",
+            "220 OK NOTIFICATION SET\r\n",
+        ),
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        (
+            "Hello\r\n.\r\n",
+            "225-21\r\n225 OK MESSAGE QUEUED\r\n701-21\r\n701-test\r\n701 BEGIN\r\n702-21\r\n702-test\r\n702 END\r\n",
+        ),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    let (mut client, events) = client.subscribe_events()?;
+    client.send(Request::Speak)?;
+    assert_eq!(Response::ReceivingData, client.receive()?);
+    client.send(Request::SendLine(String::from("Hello")))?;
+    assert_eq!(Response::MessageQueued, client.receive()?);
+    assert_eq!(Event::begin("21", "test"), events.recv().unwrap());
+    assert_eq!(Event::end("21", "test"), events.recv().unwrap());
+    drop(client);
+    // The thread has ended and the channel is closed.
+    assert!(events.recv().is_err());
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn subscribe_events_without_quit() -> ClientResult<()> {
+    use std::os::unix::net::UnixListener;
+
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let listener = UnixListener::bind(&socket_path)?;
+    let (release, released) = std::sync::mpsc::channel::<()>();
+    // The server keeps the connection open until the client is dropped.
+    let handle = std::thread::spawn(move || -> io::Result<()> {
+        let (stream, _) = listener.accept()?;
+        let mut input = io::BufReader::new(stream.try_clone()?);
+        let mut output = stream;
+        for answer in [SET_CLIENT_COMMUNICATION.1, "220 OK NOTIFICATION SET\r\n"] {
+            let mut line = String::new();
+            input.read_line(&mut line)?;
+            output.write_all(answer.as_bytes())?;
+        }
+        let _ = released.recv();
+        Ok(())
+    });
+    let mut client = ssip_client_async::fifo::Builder::new()
+        .path(&socket_path)
+        .build()?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?
+        .forget_quit();
+    let (client, _events) = client.subscribe_events()?;
+    drop(client);
+    release.send(()).unwrap();
+    handle.join().unwrap()?;
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn speech_builder() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self SSML_MODE on\r\n", "219 OK SSML MODE SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "<speak>Hello<mark name=\"w\"/>\r\nworld</speak>\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n",
+            ),
+            ("SET self SSML_MODE off\r\n", "219 OK SSML MODE SET\r\n"),
+        ],
+        |client| {
+            let (id, marks) = SpeechBuilder::new()
+                .text("Hello")
+                .mark("w")
+                .text("\nworld")
+                .speak(client)
+                .unwrap();
+            assert_eq!(21, id);
+            assert_eq!(vec![String::from("w")], marks);
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_with_boundaries() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self SSML_MODE on\r\n", "219 OK SSML MODE SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "<speak><mark name=\"b0\"/>Hi. <mark name=\"b1\"/>Bye</speak>\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n",
+            ),
+            ("SET self SSML_MODE off\r\n", "219 OK SSML MODE SET\r\n"),
+        ],
+        |client| {
+            let (id, offsets) = client
+                .say_with_boundaries("Hi. Bye", Boundary::Sentence)
+                .unwrap();
+            assert_eq!(21, id);
+            assert_eq!(2, offsets.len());
+            assert_eq!(Some(&0), offsets.get("b0"));
+            assert_eq!(Some(&4), offsets.get("b1"));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn set_default_priority() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self PRIORITY message\r\n", "202 OK PRIORITY SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(Priority::Text, client.priority());
+            client.set_default_priority(Priority::Message).unwrap();
+            assert_eq!(Priority::Message, client.priority());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_line_with_priority() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self PRIORITY important\r\n", "202 OK PRIORITY SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Fire\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SET self PRIORITY text\r\n", "202 OK PRIORITY SET\r\n"),
+            ("SET self PRIORITY important\r\n", "202 OK PRIORITY SET\r\n"),
+            ("SPEAK\r\n", "301 ERR NOT IMPLEMENTED\r\n"),
+            ("SET self PRIORITY text\r\n", "202 OK PRIORITY SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(
+                21,
+                client
+                    .say_line_with_priority(Priority::Important, "Fire")
+                    .unwrap()
+            );
+            assert_eq!(Priority::Text, client.priority());
+            assert!(client
+                .say_line_with_priority(Priority::Important, "Fire")
+                .is_err());
+            assert_eq!(Priority::Text, client.priority());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_key_and_char_with_priority() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self PRIORITY notification\r\n",
+                "202 OK PRIORITY SET\r\n"
+            ),
+            ("KEY space\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SET self PRIORITY text\r\n", "202 OK PRIORITY SET\r\n"),
+            ("SET self PRIORITY message\r\n", "202 OK PRIORITY SET\r\n"),
+            ("CHAR a\r\n", "225-23\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SET self PRIORITY text\r\n", "202 OK PRIORITY SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(
+                22,
+                client.say_key_with_priority(None, KeyName::Space).unwrap()
+            );
+            assert_eq!(
+                23,
+                client
+                    .say_char_with_priority(Some(Priority::Message), 'a')
+                    .unwrap()
+            );
+            assert_eq!(Priority::Text, client.priority());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn is_readable() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("STOP self\r\n", "210 OK STOPPED\r\n"),
+        ],
+        |client| {
+            assert!(!client.is_readable()?);
+            client.stop(MessageScope::Last).unwrap();
+            let mut tries = 0;
+            while !client.is_readable()? {
+                assert!(tries < 100, "answer not received");
+                std::thread::sleep(Duration::from_millis(10));
+                tries += 1;
+            }
+            client.check_status(OK_STOPPED).unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn events_before_answer() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SPEAK\r\n",
+                "702-20\r\n702-test\r\n702 END\r\n230 OK RECEIVING DATA\r\n",
+            ),
+            (
+                "Hello\r\n.\r\n",
+                "701-21\r\n701-test\r\n701 BEGIN\r\n225-22\r\n225 OK MESSAGE QUEUED\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(22, client.say_line("Hello").unwrap());
+            assert_eq!(
+                vec![Event::end("20", "test"), Event::begin("21", "test")],
+                client.drain_events()
+            );
+            assert!(client.drain_events().is_empty());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn lenient_line_endings() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("STOP self\r\n", "210 OK STOPPED\n"),
+            ("GET RATE\r\n", "251-0\n251 OK GET RETURNED\n"),
+        ],
+        |client| {
+            assert!(client
+                .stop(MessageScope::Last)
+                .unwrap()
+                .check_status(OK_STOPPED)
+                .is_err());
+            client.set_lenient_line_endings(true);
+            assert_eq!(0, client.get_rate().unwrap().receive_u8().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn server_disconnect() -> ClientResult<()> {
+    test_client!(&[SET_CLIENT_COMMUNICATION], |client| {
+        assert!(matches!(client.receive(), Err(ClientError::Disconnected)));
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn keepalive() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("GET RATE\r\n", "251-0\r\n251 OK GET RETURNED\r\n"),
+        ],
+        |client| {
+            assert!(!client.keepalive().unwrap());
+            client.set_keepalive(Some(Duration::ZERO));
+            assert!(client.keepalive().unwrap());
+            // The server has closed the connection.
+            assert!(matches!(client.keepalive(), Err(ClientError::Disconnected)));
+            client.set_keepalive(None);
+            assert!(!client.keepalive().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn execute_raw() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "LIST VOICES\r\n",
+                "702-20\r\n702-test\r\n702 END\r\n249-MALE1\r\n249-FEMALE1\r\n249 OK VOICE LIST SENT\r\n"
+            ),
+            ("FOO bar\r\n", "500 ERR INVALID COMMAND\r\n"),
+            ("GET RATE\r\n", "251-10\n251 OK GET RETURNED\n"),
+        ],
+        |client| {
+            assert_eq!(
+                vec!["249-MALE1", "249-FEMALE1", "249 OK VOICE LIST SENT"],
+                client.execute_raw("LIST VOICES").unwrap()
+            );
+            // The event received before the answer is queued.
+            assert_eq!(
+                vec![Event::end("20", "test")],
+                client.drain_events()
+            );
+            assert_eq!(
+                vec!["500 ERR INVALID COMMAND"],
+                client.execute_raw("FOO bar").unwrap()
+            );
+            assert!(matches!(
+                client.execute_raw("SPEAK\r\nhello"),
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidInput
+            ));
+            client.set_lenient_line_endings(true);
+            assert_eq!(
+                vec!["251-10", "251 OK GET RETURNED"],
+                client.execute_raw("GET RATE").unwrap()
+            );
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn execute() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "LIST VOICES\r\n",
+                "249-MALE1\r\n249-FEMALE1\r\n249 OK VOICE LIST SENT\r\n"
+            ),
+            ("FOO bar\r\n", "500 ERR INVALID COMMAND\r\n"),
+        ],
+        |client| {
+            let (lines, status) = client.execute("LIST VOICES").unwrap();
+            assert_eq!(vec!["MALE1", "FEMALE1"], lines);
+            assert_eq!(OK_VOICES_LIST_SENT, status.code);
+            assert!(matches!(
+                client.execute("FOO bar"),
+                Err(ClientError::Ssip(StatusLine { code: 500, .. }))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn escape_policy() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "..hidden <b>\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n"
+            ),
+            ("SET self SSML_MODE on\r\n", "219 OK SSML MODE SET\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Tom &amp; Jerry\r\n.\r\n",
+                "225-22\r\n225 OK MESSAGE QUEUED\r\n"
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("<speak/>\r\n.\r\n", "225-23\r\n225 OK MESSAGE QUEUED\r\n"),
+            // A second dot after the text would be read here instead of the command.
+            ("SET self SSML_MODE off\r\n", "219 OK SSML MODE SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(EscapePolicy::SsipDots, client.escape_policy());
+            assert_eq!(21, client.say_line(".hidden <b>").unwrap());
+            client
+                .set_ssml_mode(true)
+                .unwrap()
+                .check_status(OK_SSML_MODE_SET)
+                .unwrap();
+            assert_eq!(EscapePolicy::XmlEntities, client.escape_policy());
+            assert_eq!(22, client.say_line("Tom & Jerry").unwrap());
+            assert_eq!(
+                23,
+                client
+                    .say_line_escaped("<speak/>", EscapePolicy::None)
+                    .unwrap()
+            );
+            assert!(client
+                .say_line_escaped("one\n.\ntwo", EscapePolicy::None)
+                .is_err());
+            client
+                .set_ssml_mode(false)
+                .unwrap()
+                .check_status(OK_SSML_MODE_SET)
+                .unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn index_marks_of_message() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("One\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Two\r\n.\r\n",
+                "225-22\r\n225 OK MESSAGE QUEUED\r\n700-21\r\n700-test\r\n700-a\r\n700 INDEX MARK\r\n700-22\r\n700-test\r\n700-b\r\n700 INDEX MARK\r\n700-21\r\n700-test\r\n700-c\r\n700 INDEX MARK\r\n",
+            ),
+        ],
+        |client| {
+            let first = client.say_line("One").unwrap();
+            let second = client.say_line("Two").unwrap();
+            let events = (0..3)
+                .map(|_| client.receive_event().unwrap())
+                .collect::<Vec<_>>();
+            let marks = |id: MessageId| {
+                events
+                    .iter()
+                    .filter(|event| event.matches(&id))
+                    .map(|event| event.ntype.clone())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                vec![
+                    EventType::IndexMark(String::from("a")),
+                    EventType::IndexMark(String::from("c"))
+                ],
+                marks(first)
+            );
+            assert_eq!(vec![EventType::IndexMark(String::from("b"))], marks(second));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn build_with_retry() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 1] = [SET_CLIENT_COMMUNICATION];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let mut builder = ssip_client_async::fifo::Builder::new();
+    builder.path(&socket_path);
+    assert!(builder
+        .build_with_retry(Duration::from_millis(50), Duration::from_millis(10))
+        .is_err());
+    let server_path = socket_path.clone();
+    let server = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        server::run_unix(&server_path, &COMMUNICATION)
+    });
+    let mut client = builder.build_with_retry(Duration::from_secs(5), Duration::from_millis(10))?;
+    client
+        .set_client_name(ClientName::new("test", "test"))?
+        .check_client_name_set()?;
+    server.join().unwrap()?.join().unwrap()?;
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn stop_many() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("STOP 21\r\n", "210 OK STOPPED\r\n"),
+            ("STOP 7\r\n", "406 ERR ID DOESNT EXIST\r\n"),
+            ("STOP 22\r\n", "210 OK STOPPED\r\n"),
+        ],
+        |client| {
+            let statuses = client.stop_many(&[21, 7, 22]).unwrap();
+            assert_eq!(3, statuses.len());
+            assert!(matches!(
+                statuses[0],
+                Ok(StatusLine {
+                    code: OK_STOPPED,
+                    ..
+                })
+            ));
+            assert!(matches!(
+                statuses[1],
+                Err(ClientError::UnknownId { id: 7, .. })
+            ));
+            assert!(matches!(
+                statuses[2],
+                Ok(StatusLine {
+                    code: OK_STOPPED,
+                    ..
+                })
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn stop_many_disconnected() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("STOP 21\r\n", "406 ERR ID DOESNT EXIST\r\n"),
+        ],
+        |client| {
+            // The server closes the connection before the second id.
+            assert!(matches!(
+                client.stop_many(&[21, 22]),
+                Err(ClientError::Io(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn unknown_id() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("CANCEL 21\r\n", "406 ERR ID DOESNT EXIST\r\n"),
+            ("SET 7 RATE 10\r\n", "402 ERR NO SUCH CLIENT\r\n"),
+            ("STOP self\r\n", "406 ERR ID DOESNT EXIST\r\n"),
+            ("PAUSE 8\r\n", "402 ERR NO SUCH CLIENT\r\n"),
+            ("PAUSE 8\r\n", "406 ERR ID DOESNT EXIST\r\n"),
+        ],
+        |client| {
+            match client
+                .cancel(MessageScope::Message(21))
+                .unwrap()
+                .check_status(OK_CANCELED)
+            {
+                Err(ClientError::UnknownId { id, status }) => {
+                    assert_eq!(21, id);
+                    assert_eq!(ERR_ID_NOT_EXIST, status.code);
+                }
+                result => panic!("unexpected result: {:?}", result.map(|_| ())),
+            }
+            assert!(matches!(
+                client
+                    .set_rate(ClientScope::Client(7), Rate::try_from(10).unwrap())
+                    .unwrap()
+                    .check_status(OK_RATE_SET),
+                Err(ClientError::UnknownId { id: 7, .. })
+            ));
+            // Without an id in the request, the error is left as is.
+            assert!(matches!(
+                client
+                    .stop(MessageScope::Last)
+                    .unwrap()
+                    .check_status(OK_STOPPED),
+                Err(ClientError::Ssip(_))
+            ));
+            // The id is labeled by the scope written, whatever the method called.
+            assert!(matches!(
+                client
+                    .send(Request::PauseClient(ClientScope::Client(8)))
+                    .unwrap()
+                    .check_status(OK_PAUSED),
+                Err(ClientError::UnknownId { id: 8, .. })
+            ));
+            assert!(matches!(
+                client
+                    .send(Request::PauseClient(ClientScope::Client(8)))
+                    .unwrap()
+                    .check_status(OK_PAUSED),
+                Err(ClientError::Ssip(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[cfg(not(feature = "async-mio"))]
+fn assert_send<T: Send>() {}
+
+#[cfg(not(feature = "async-mio"))]
+fn assert_sync<T: Sync>() {}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn send_and_sync() {
+    assert_send::<Client<UnixStream>>();
+    assert_sync::<Client<UnixStream>>();
+    assert_send::<Client<TcpStream>>();
+    assert_sync::<Client<TcpStream>>();
+    assert_send::<client::ClientReader<UnixStream>>();
+    assert_send::<client::ClientWriter<UnixStream>>();
+    assert_send::<client::EventClient<UnixStream>>();
+    assert_send::<SharedClient<UnixStream>>();
+    assert_sync::<SharedClient<UnixStream>>();
+    assert_send::<QueuedClient<UnixStream>>();
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn from_raw_fd() -> ClientResult<()> {
+    use std::os::unix::{io::IntoRawFd, net::UnixDatagram};
+    const COMMUNICATION: [(&str, &str); 2] = [
+        SET_CLIENT_COMMUNICATION,
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let fd = UnixStream::connect(&socket_path)?.into_raw_fd();
+    let mut client = unsafe { Client::from_raw_fd(fd, &ClientName::new("test", "test"))? };
+    assert_eq!("CLIENT NAME SET", client.handshake().unwrap().message);
+    client.quit()?.check_status(OK_BYE)?;
+    handle.join().unwrap().unwrap();
+
+    let (datagram, _) = UnixDatagram::pair()?;
+    let file = std::fs::File::open(socket_dir.path())?;
+    for fd in [datagram.into_raw_fd(), file.into_raw_fd()] {
+        match unsafe { Client::from_raw_fd(fd, &ClientName::new("test", "test")) } {
+            Err(ClientError::Io(err)) => assert_eq!(io::ErrorKind::InvalidInput, err.kind()),
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_chunks() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "First sentence. Second one.\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n",
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Short.\r\n.\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("The end.\r\n.\r\n", "225-23\r\n225 OK MESSAGE QUEUED\r\n"),
+        ],
+        |client| {
+            const TEXT: &str = "First sentence. Second one.";
+            assert_eq!(
+                Some(speech::DEFAULT_MAX_MESSAGE_BYTES),
+                client.max_message_bytes()
+            );
+            client.set_max_message_bytes(Some(10));
+            // Chunking is disabled by default.
+            assert_eq!(vec![21], client.say_chunks(TEXT).unwrap());
+            client.set_chunking(true);
+            assert_eq!(23, client.say_line("Short. The end.").unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn send_no_wait() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self RATE 10\r\n", "203 OK RATE SET\r\n"),
+            ("SET self PITCH 100\r\n", "410 ERR PITCH TOO HIGH\r\n"),
+            ("GET VOLUME\r\n", "251-20\r\n251 OK GET\r\n"),
+        ],
+        |client| {
+            client
+                .send_no_wait(Request::SetRate(
+                    ClientScope::Current,
+                    Rate::try_from(10).unwrap(),
+                ))
+                .unwrap()
+                .send_no_wait(Request::SetPitch(
+                    ClientScope::Current,
+                    Pitch::try_from(100).unwrap(),
+                ))
+                .unwrap();
+            assert_eq!(2, client.pending_answers());
+            assert_eq!(20, client.get_volume().unwrap().receive_u8().unwrap());
+            assert_eq!(0, client.pending_answers());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn help_for() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
             (
-                "Hello, world\r\n.\r\n",
-                "225-21\r\n225 OK MESSAGE QUEUED\r\n",
+                "HELP SET\r\n",
+                "248-SET [self|all|id] RATE <n>\r\n248-SET [self|all|id] PITCH <n>\r\n248 OK HELP SENT\r\n",
             ),
+            ("HELP FOO\r\n", "500 ERR INVALID COMMAND\r\n"),
+            ("HELP STOP\r\n", "300 ERR INTERNAL\r\n"),
+            ("HELP CHAR\r\n", "510 ERR MISSING PARAMETER\r\n"),
         ],
         |client| {
             assert_eq!(
-                21,
-                client
-                    .speak()
-                    .unwrap()
-                    .check_status(OK_RECEIVING_DATA)
-                    .unwrap()
-                    .send_line("Hello, world")
-                    .unwrap()
-                    .receive_message_id()
-                    .unwrap()
+                vec!["SET [self|all|id] RATE <n>", "SET [self|all|id] PITCH <n>"],
+                client.help_for("SET").unwrap()
+            );
+            assert!(client.help_for("FOO").unwrap().is_empty());
+            assert!(matches!(
+                client.help_for("STOP"),
+                Err(ClientError::Ssip(StatusLine { code: 300, .. }))
+            ));
+            assert!(matches!(
+                client.help_for("CHAR"),
+                Err(ClientError::Ssip(StatusLine {
+                    code: ERR_MISSING_PARAMETER,
+                    ..
+                }))
+            ));
+            assert!(matches!(
+                client.help_for("SET RATE"),
+                Err(ClientError::Io(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn pause_with_context() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self PAUSE_CONTEXT 2\r\n",
+                "217 OK PAUSE CONTEXT SET\r\n"
+            ),
+            (
+                "SET self PAUSE_CONTEXT 1\r\nPAUSE self\r\n",
+                "217 OK PAUSE CONTEXT SET\r\n211 OK PAUSED\r\n",
+            ),
+            ("RESUME self\r\n", "212 OK RESUMED\r\n"),
+            (
+                "SET self PAUSE_CONTEXT 2\r\n",
+                "217 OK PAUSE CONTEXT SET\r\n"
+            ),
+            (
+                "SET self PAUSE_CONTEXT 3\r\nPAUSE self\r\n",
+                "217 OK PAUSE CONTEXT SET\r\n211 OK PAUSED\r\n",
+            ),
+            ("RESUME self\r\n", "212 OK RESUMED\r\n"),
+        ],
+        |client| {
+            client
+                .set_pause_context(ClientScope::Current, 2)
+                .unwrap()
+                .check_status(OK_PAUSE_CONTEXT_SET)
+                .unwrap()
+                .pause_with_context(MessageScope::Last, 1)
+                .unwrap()
+                .resume_with_context(MessageScope::Last, true)
+                .unwrap()
+                .pause_with_context(MessageScope::Last, 3)
+                .unwrap()
+                .resume_with_context(MessageScope::Last, false)
+                .unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn default_scope() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self RATE 10\r\n", "203 OK RATE SET\r\n"),
+            ("STOP self\r\n", "210 OK STOPPED\r\n"),
+        ],
+        |client| {
+            client
+                .set_rate((), Rate::try_from(10).unwrap())
+                .unwrap()
+                .check_status(OK_RATE_SET)
+                .unwrap();
+            client.stop(()).unwrap().check_status(OK_STOPPED).unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_display() -> ClientResult<()> {
+    struct Lines;
+    impl std::fmt::Display for Lines {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "first\n.\nlast")
+        }
+    }
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("42\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "first\r\n..\r\nlast\r\n.\r\n",
+                "225-22\r\n225 OK MESSAGE QUEUED\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(21, client.say(42).unwrap());
+            assert_eq!(22, client.say(Lines).unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(all(feature = "normalize", not(feature = "async-mio")))]
+fn say_normalized() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Cafe\u{301}\u{7}\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n"
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Café\r\n.\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+        ],
+        |client| {
+            let text = "Cafe\u{301}\u{7}";
+            assert_eq!(21, client.say_line(text).unwrap());
+            client.set_normalizer(
+                TextNormalizer::new()
+                    .nfc(true)
+                    .control_chars(ControlChars::Strip),
             );
+            assert_eq!(22, client.say_line(text).unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn event_loop() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n701-21\r\n701-test\r\n701 BEGIN\r\n702-21\r\n702-test\r\n702 END\r\n",
+            ),
+        ],
+        |client| {
+            let id = client.say_line("Hello").unwrap();
+            let mut events = Vec::new();
+            client
+                .run_event_loop(|event| {
+                    let end = event.ntype == EventType::End;
+                    events.push(event.ntype);
+                    if end {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                })
+                .unwrap();
+            assert_eq!(vec![EventType::Begin, EventType::End], events);
+            assert_eq!(MessageState::Finished, client.message_status(&id));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(all(unix, not(feature = "async-mio")))]
+fn event_loop_with_commands() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            (
+                "SET self RATE 10\r\n",
+                "203 OK RATE SET\r\n702-21\r\n702-test\r\n702 END\r\n",
+            ),
+        ],
+        |client| {
+            client.say_line("Hello").unwrap();
+            let (sender, commands) = std::sync::mpsc::channel();
+            sender
+                .send(Request::SetRate(
+                    ClientScope::Current,
+                    Rate::try_from(10).unwrap(),
+                ))
+                .unwrap();
+            let mut events = Vec::new();
+            client
+                .run_event_loop_with_commands(&commands, Duration::from_millis(10), |event| {
+                    events.push(event.ntype);
+                    ControlFlow::Break(())
+                })
+                .unwrap();
+            assert_eq!(vec![EventType::End], events);
+            assert_eq!(0, client.pending_answers());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn receive_synthesis_voices_lenient() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "LIST SYNTHESIS_VOICES\r\n",
+                "249-klatt\tnone\tnone\r\n249-\tfr\tnone\r\n249 OK VOICE LIST SENT\r\n",
+            ),
+        ],
+        |client| {
+            let (voices, errors) = client
+                .list_synthesis_voices()
+                .unwrap()
+                .receive_synthesis_voices_lenient()
+                .unwrap();
+            assert_eq!(vec![SynthesisVoice::new("klatt", None, None)], voices);
+            assert_eq!(1, errors.len());
+            assert_eq!("\tfr\tnone", errors[0].0);
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn retry() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "431 ERR QUEUE TOO BIG\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("STOP 21\r\n", "406 ERR ID NOT EXIST\r\n"),
+            ("SET self RATE 0\r\n", "409 ERR RATE TOO HIGH\r\n"),
+            ("SET self PITCH 0\r\n", "510 ERR INVALID PARAMETER\r\n"),
+        ],
+        |client| {
+            client.with_retry(RetryPolicy {
+                max_retries: 1,
+                initial_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+            });
+            assert_eq!(21, client.retry(|client| client.say_line("Hello")).unwrap());
+            // Permanent errors are returned without sending the request again.
+            let result = client.retry(|client| {
+                client
+                    .stop(MessageScope::Message(21))?
+                    .check_status(OK_STOPPED)
+                    .map(|_| ())
+            });
+            assert!(matches!(result, Err(ClientError::UnknownId { .. })));
+            let result = client.retry(|client| {
+                client
+                    .set_rate(ClientScope::Current, Rate::default())?
+                    .check_status(OK_RATE_SET)
+                    .map(|_| ())
+            });
+            assert!(matches!(result, Err(ClientError::Ssip(_))));
+            let result = client.retry(|client| {
+                client
+                    .set_pitch(ClientScope::Current, Pitch::default())?
+                    .check_status(OK_PITCH_SET)
+                    .map(|_| ())
+            });
+            assert!(matches!(result, Err(ClientError::Ssip(_))));
+            // A timeout may leave the answer unread, the exchange is not run again.
+            let mut attempts = 0;
+            let result: ClientResult<()> = client.retry(|_| {
+                attempts += 1;
+                Err(ClientError::Timeout)
+            });
+            assert!(matches!(result, Err(ClientError::Timeout)));
+            assert_eq!(1, attempts);
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_all() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("First\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Second\r\n.\r\n", "225-22\r\n225 OK MESSAGE QUEUED\r\n"),
+        ],
+        |client| {
+            assert_eq!(vec![21, 22], client.say_all(["First", "Second"]).unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_all_partial() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("First\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "401 ERR NO CLIENT\r\n"),
+        ],
+        |client| {
+            match client.say_all(["First", "Second"]) {
+                Err(ClientError::PartialBatch { ids, source }) => {
+                    assert_eq!(vec![21], ids);
+                    assert!(matches!(*source, ClientError::Ssip(_)));
+                }
+                _ => panic!("expecting a partial batch error"),
+            }
             Ok(())
         }
     )
@@ -223,12 +1999,61 @@ fn set_debug() -> ClientResult<()> {
             ),
         ],
         |client| {
-            let output = client
-                .set_debug(true)
+            let output = client.set_debug(true).unwrap();
+            assert_eq!(
+                Some(std::path::PathBuf::from(
+                    "/run/user/100/speech-dispatcher/log/debug"
+                )),
+                output
+            );
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn unset_debug() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET all DEBUG off\r\n", "262 OK DEBUGGING SET\r\n"),
+        ],
+        |client| {
+            assert_eq!(None, client.set_debug(false).unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn reset_parameters() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self RATE 0\r\nSET self PITCH 0\r\nSET self VOLUME 100\r\nSET self PUNCTUATION none\r\nSET self SPELLING off\r\n",
+                "203 OK RATE SET\r\n204 OK PITCH SET\r\n218 OK VOLUME SET\r\n205 OK PUNCTUATION SET\r\n207 OK SPELLING SET\r\n",
+            ),
+            (
+                "SET self RATE 0\r\nSET self PITCH 0\r\nSET self VOLUME 100\r\nSET self PUNCTUATION none\r\nSET self SPELLING off\r\n",
+                "203 OK RATE SET\r\n304 ERR COULDNT SET PITCH\r\n314 ERR COULDNT SET VOLUME\r\n205 OK PUNCTUATION SET\r\n207 OK SPELLING SET\r\n",
+            ),
+            ("STOP self\r\n", "210 OK STOPPED\r\n"),
+        ],
+        |client| {
+            assert_eq!(OK_SPELLING_SET, client.reset_parameters().unwrap().code);
+            match client.reset_parameters() {
+                Err(ClientError::Ssip(status)) => assert_eq!(ERR_COULDNT_SET_PITCH, status.code),
+                _ => panic!("expecting the pitch error"),
+            }
+            // All the answers have been consumed.
+            client
+                .stop(MessageScope::Last)
                 .unwrap()
-                .receive_string(OK_DEBUG_SET)
+                .check_status(OK_STOPPED)
                 .unwrap();
-            assert_eq!("/run/user/100/speech-dispatcher/log/debug", output);
             Ok(())
         }
     )
@@ -243,6 +2068,41 @@ test_setter!(
     "espeak-ng",
 );
 
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn set_output_module_fuzzy() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "LIST OUTPUT_MODULES\r\n",
+                "250-espeak-NG\r\n250-festival\r\n250 OK MODULE LIST SENT\r\n",
+            ),
+            (
+                "SET self OUTPUT_MODULE espeak-NG\r\n",
+                "216 OK OUTPUT MODULE SET\r\n"
+            ),
+            (
+                "LIST OUTPUT_MODULES\r\n",
+                "250-espeak-NG\r\n250-festival\r\n250 OK MODULE LIST SENT\r\n",
+            ),
+        ],
+        |client| {
+            assert_eq!(
+                "espeak-NG",
+                client
+                    .set_output_module_fuzzy(ClientScope::Current, "espeak-ng")
+                    .unwrap()
+            );
+            assert!(matches!(
+                client.set_output_module_fuzzy(ClientScope::Current, "flite"),
+                Err(ClientError::InvalidType(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
 test_getter!(
     get_output_module,
     "GET OUTPUT_MODULE\r\n",
@@ -280,7 +2140,7 @@ test_setter!(
     "203 OK RATE SET\r\n",
     203,
     ClientScope::Current,
-    15,
+    Rate::try_from(15).unwrap(),
 );
 
 test_getter!(
@@ -298,7 +2158,7 @@ test_setter!(
     "218 OK VOLUME SET\r\n",
     218,
     ClientScope::Current,
-    80,
+    Volume::try_from(80).unwrap(),
 );
 
 test_getter!(
@@ -311,22 +2171,67 @@ test_getter!(
 );
 
 test_getter!(
-    get_pitch,
+    get_pitch,
+    receive_i8,
+    (),
+    "GET PITCH\r\n",
+    "251-0\r\n251 OK GET RETURNED\r\n",
+    0
+);
+
+test_setter!(
+    set_pitch,
+    "SET self PITCH 10\r\n",
+    "204 OK PITCH SET\r\n",
+    204,
+    ClientScope::Current,
+    Pitch::try_from(10).unwrap(),
+);
+
+test_setter!(
+    set_pitch_range,
+    "SET self PITCH_RANGE 20\r\n",
+    "263 OK PITCH RANGE SET\r\n",
+    263,
+    ClientScope::Current,
+    PitchRange::try_from(20).unwrap(),
+);
+
+test_getter!(
+    get_pitch_range,
     receive_i8,
     (),
-    "GET PITCH\r\n",
+    "GET PITCH_RANGE\r\n",
     "251-0\r\n251 OK GET RETURNED\r\n",
     0
 );
 
-test_setter!(
-    set_pitch,
-    "SET self PITCH 10\r\n",
-    "204 OK PITCH SET\r\n",
-    204,
-    ClientScope::Current,
-    10,
-);
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn set_pitch_range_unsupported() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self PITCH_RANGE 20\r\n",
+                "340 ERR COULDNT SET PITCH RANGE\r\n"
+            ),
+        ],
+        |client| {
+            match client
+                .set_pitch_range(ClientScope::Current, PitchRange::try_from(20).unwrap())
+                .unwrap()
+                .check_status(OK_PITCH_RANGE_SET)
+            {
+                Err(ClientError::Ssip(status)) => {
+                    assert_eq!(ERR_COULDNT_SET_PITCH_RANGE, status.code)
+                }
+                _ => panic!("expecting an SSIP error"),
+            }
+            Ok(())
+        }
+    )
+}
 
 test_setter!(
     set_ssml_mode,
@@ -387,6 +2292,167 @@ test_list!(
     &[ "MALE1", "MALE2", "FEMALE1", "FEMALE2", "CHILD_MALE", "CHILD_FEMALE" ]
 );
 
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn get_settings() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("GET RATE\r\n", "251--20\r\n251 OK GET RETURNED\r\n"),
+            ("GET PITCH\r\n", "251-10\r\n251 OK GET RETURNED\r\n"),
+            ("GET VOLUME\r\n", "300 ERR UNKNOWN\r\n"),
+            ("GET VOICE_TYPE\r\n", "251-MALE1\r\n251 OK GET RETURNED\r\n"),
+            ("GET LANGUAGE\r\n", "251-fr\r\n251 OK GET RETURNED\r\n"),
+            ("GET PUNCTUATION\r\n", "500 ERR INVALID COMMAND\r\n"),
+            ("GET RATE\r\n", "251-0\r\n251 OK GET RETURNED\r\n"),
+            ("GET PITCH\r\n", "251-0\r\n251 OK GET RETURNED\r\n"),
+            ("GET VOLUME\r\n", "251-0\r\n251 OK GET RETURNED\r\n"),
+            ("GET VOICE_TYPE\r\n", "251-MALE1\r\n251 OK GET RETURNED\r\n"),
+            ("GET LANGUAGE\r\n", "251-fr\r\n251 OK GET RETURNED\r\n"),
+            ("GET PUNCTUATION\r\n", "251-most\r\n251 OK GET RETURNED\r\n"),
+        ],
+        |client| {
+            assert_eq!(
+                Settings {
+                    rate: Some(Rate::try_from(-20).unwrap()),
+                    pitch: Some(Pitch::try_from(10).unwrap()),
+                    volume: None,
+                    voice: Some(String::from("MALE1")),
+                    language: Some(String::from("fr")),
+                    punctuation: None,
+                },
+                client.get_settings().unwrap()
+            );
+            assert_eq!(
+                Some(PunctuationMode::Most),
+                client.get_settings().unwrap().punctuation
+            );
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn apply_settings() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET all RATE -20\r\n", "203 OK RATE SET\r\n"),
+            ("SET all VOICE_TYPE CHILD\r\n", "410 ERR INVALID VOICE\r\n"),
+            ("SET all PUNCTUATION most\r\n", "205 OK PUNCTUATION SET\r\n"),
+            ("BLOCK BEGIN\r\n", "260 OK INSIDE BLOCK\r\n"),
+            ("SET self LANGUAGE fr\r\n", "201 OK LANGUAGE SET\r\n"),
+            ("BLOCK END\r\n", "261 OK OUTSIDE BLOCK\r\n"),
+        ],
+        |client| {
+            let settings = Settings {
+                rate: Some(Rate::try_from(-20).unwrap()),
+                voice: Some(String::from("CHILD")),
+                punctuation: Some(PunctuationMode::Most),
+                ..Settings::default()
+            };
+            match client.apply_settings(ClientScope::All, &settings) {
+                Err(ClientError::SettingsFailed { failures }) => {
+                    assert_eq!(1, failures.len());
+                    assert_eq!("voice", failures[0].0);
+                }
+                result => panic!("unexpected result: {:?}", result),
+            }
+            let settings = Settings {
+                language: Some(String::from("fr")),
+                ..Settings::default()
+            };
+            client.apply_settings_in_block(&settings).unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn apply_profile() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SET self RATE 30\r\n", "203 OK RATE SET\r\n"),
+            (
+                "SET self SYNTHESIS_VOICE lancashire\r\n",
+                "209 OK VOICE SET\r\n"
+            ),
+        ],
+        |client| {
+            let mut profile = Profile::new(
+                ClientName::new("test", "test"),
+                Settings {
+                    rate: Some(Rate::try_from(30).unwrap()),
+                    ..Settings::default()
+                },
+                SynthesisVoice::new("", None, None),
+            );
+            // Nothing is sent for an invalid profile.
+            assert!(client.apply_profile(&profile).is_err());
+            profile.voice.name = String::from("lancashire");
+            client.apply_profile(&profile).unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn list_voices() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "LIST VOICES\r\n",
+                "249-MALE1\r\n249-FEMALE1\r\n249 OK VOICE LIST SENT\r\n"
+            ),
+        ],
+        |client| {
+            assert_eq!(vec!["MALE1", "FEMALE1"], client.list_voices().unwrap());
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn list_typed() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "LIST OUTPUT_MODULES\r\n",
+                "250-espeak-ng\r\n250-festival\r\n250 OK MODULE LIST SENT\r\n"
+            ),
+            ("LIST OUTPUT_MODULES\r\n", "250 OK MODULE LIST SENT\r\n"),
+            (
+                "LIST SYNTHESIS_VOICES\r\n",
+                "249-Amharic\tam\tnone\r\n249 OK VOICE LIST SENT\r\n"
+            ),
+            ("LIST VOICES\r\n", "250 OK MODULE LIST SENT\r\n"),
+        ],
+        |client| {
+            assert_eq!(
+                vec!["espeak-ng", "festival"],
+                client.output_modules().unwrap()
+            );
+            assert!(client.output_modules().unwrap().is_empty());
+            assert_eq!(
+                vec![SynthesisVoice::new("Amharic", Some("am"), None)],
+                client.synthesis_voices().unwrap()
+            );
+            assert!(matches!(
+                client.list_voices(),
+                Err(ClientError::UnexpectedStatus(OK_OUTPUT_MODULES_LIST_SENT))
+            ));
+            Ok(())
+        }
+    )
+}
+
 #[test]
 #[cfg(not(feature = "async-mio"))]
 fn list_synthesis_voices() -> ClientResult<()> {
@@ -413,6 +2479,76 @@ fn list_synthesis_voices() -> ClientResult<()> {
     )
 }
 
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn set_voice_for_language() -> ClientResult<()> {
+    const VOICES: &str = "249-German\tde\tnone\r\n249-Austrian\tde-AT\tnone\r\n249-Swiss\tde\tCH\r\n249 OK VOICE LIST SENT\r\n";
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("LIST SYNTHESIS_VOICES\r\n", VOICES),
+            (
+                "SET self SYNTHESIS_VOICE German\r\n",
+                "209 OK VOICE SET\r\n"
+            ),
+            ("LIST SYNTHESIS_VOICES\r\n", VOICES),
+            (
+                "SET self SYNTHESIS_VOICE Austrian\r\n",
+                "209 OK VOICE SET\r\n"
+            ),
+            ("LIST SYNTHESIS_VOICES\r\n", VOICES),
+            ("SET self SYNTHESIS_VOICE Swiss\r\n", "209 OK VOICE SET\r\n"),
+            ("LIST SYNTHESIS_VOICES\r\n", VOICES),
+        ],
+        |client| {
+            for (lang, name) in [("de", "German"), ("de-AT", "Austrian"), ("de_CH", "Swiss")] {
+                let voice = client
+                    .set_voice_for_language(ClientScope::Current, lang)
+                    .unwrap();
+                assert_eq!(name, voice.name);
+            }
+            assert!(matches!(
+                client.set_voice_for_language(ClientScope::Current, "fr"),
+                Err(ClientError::InvalidType(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn set_synthesis_voice_or_fallback() -> ClientResult<()> {
+    const VOICES: &str =
+        "249-German\tde\tnone\r\n249-Austrian\tde-AT\tnone\r\n249 OK VOICE LIST SENT\r\n";
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("LIST SYNTHESIS_VOICES\r\n", VOICES),
+            (
+                "SET self SYNTHESIS_VOICE Austrian\r\n",
+                "209 OK VOICE SET\r\n"
+            ),
+            ("LIST SYNTHESIS_VOICES\r\n", VOICES),
+        ],
+        |client| {
+            let voice = client
+                .set_synthesis_voice_or_fallback(
+                    ClientScope::Current,
+                    &["Swiss", "Austrian", "German"],
+                )
+                .unwrap();
+            assert_eq!("Austrian", voice.name);
+            assert_eq!(Some("de-AT"), voice.language.as_deref());
+            assert!(matches!(
+                client.set_synthesis_voice_or_fallback(ClientScope::Current, &["Swiss", "german"]),
+                Err(ClientError::InvalidType(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
 #[test]
 #[cfg(not(feature = "async-mio"))]
 fn receive_notification() -> ClientResult<()> {
@@ -450,6 +2586,205 @@ fn receive_notification() -> ClientResult<()> {
     )
 }
 
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn message_status() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello, world\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n701-21\r\n701-test\r\n701 BEGIN\r\n702-21\r\n702-test\r\n702 END\r\n",
+            ),
+        ],
+        |client| {
+            let id = client.say_line("Hello, world").unwrap();
+            assert_eq!(MessageState::Queued, client.message_status(&id));
+            assert!(!client.is_speaking());
+            client.receive_event().unwrap();
+            assert_eq!(MessageState::Speaking, client.message_status(&id));
+            assert!(client.is_speaking());
+            client.receive().unwrap();
+            assert_eq!(MessageState::Finished, client.message_status(&id));
+            assert!(!client.is_speaking());
+            assert_eq!(MessageState::Unknown, client.message_status(&99));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_line_blocking() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self NOTIFICATION begin on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            (
+                "SET self NOTIFICATION end on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            (
+                "SET self NOTIFICATION cancel on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello, world\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n702-20\r\n702-test\r\n702 END\r\n701-21\r\n701-test\r\n701 BEGIN\r\n702-21\r\n702-test\r\n702 END\r\n",
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Again\r\n.\r\n",
+                "225-22\r\n225 OK MESSAGE QUEUED\r\n703-22\r\n703-test\r\n703 CANCELED\r\n",
+            ),
+        ],
+        |client| {
+            client.say_line_blocking("Hello, world").unwrap();
+            assert_eq!(MessageState::Finished, client.message_status(&21));
+            // Notifications are already enabled.
+            client.say_line_blocking("Again").unwrap();
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn notifications_unsupported() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self NOTIFICATION begin on\r\n",
+                "409 ERR COULDNT SET NOTIFICATION\r\n"
+            ),
+            (
+                "SET self NOTIFICATION begin on\r\n",
+                "409 ERR COULDNT SET NOTIFICATION\r\n"
+            ),
+        ],
+        |client| {
+            assert!(matches!(
+                client.say_line_blocking("Hello"),
+                Err(ClientError::NotificationsUnsupported(StatusLine {
+                    code: 409,
+                    ..
+                }))
+            ));
+            // The notification is not recorded as enabled, so it's requested again.
+            assert!(matches!(
+                client.say_line_blocking("Hello"),
+                Err(ClientError::NotificationsUnsupported(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_line_when_ready() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self NOTIFICATION end on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            (
+                "SET self NOTIFICATION cancel on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello\r\n.\r\n",
+                "431 ERR QUEUE TOO BIG\r\n702-20\r\n702-test\r\n702 END\r\n"
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Hello\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            ("Again\r\n.\r\n", "431 ERR QUEUE TOO BIG\r\n"),
+        ],
+        |client| {
+            assert_eq!(21, client.say_line_when_ready("Hello").unwrap());
+            // Without waiting, the error is returned.
+            assert!(matches!(
+                client.say_line("Again"),
+                Err(ClientError::Ssip(StatusLine {
+                    code: ERR_QUEUE_FULL,
+                    ..
+                }))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_line_when_ready_unsupported() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self NOTIFICATION end on\r\n",
+                "409 ERR COULDNT SET NOTIFICATION\r\n"
+            ),
+        ],
+        |client| {
+            // Nothing is spoken when the queue can't be watched.
+            assert!(matches!(
+                client.say_line_when_ready("Hello"),
+                Err(ClientError::NotificationsUnsupported(_))
+            ));
+            Ok(())
+        }
+    )
+}
+
+#[test]
+#[cfg(not(feature = "async-mio"))]
+fn say_line_blocking_timeout() -> ClientResult<()> {
+    test_client!(
+        &[
+            SET_CLIENT_COMMUNICATION,
+            (
+                "SET self NOTIFICATION begin on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            (
+                "SET self NOTIFICATION end on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            (
+                "SET self NOTIFICATION cancel on\r\n",
+                "220 OK NOTIFICATION SET\r\n"
+            ),
+            ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+            (
+                "Hello, world\r\n.\r\n",
+                "225-21\r\n225 OK MESSAGE QUEUED\r\n701-21\r\n701-test\r\n701 BEGIN\r\n",
+            ),
+            ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+        ],
+        |client| {
+            assert!(matches!(
+                client.say_line_blocking_timeout("Hello, world", Duration::from_millis(100)),
+                Err(ClientError::Timeout)
+            ));
+            assert_eq!(Some(21), client.last_message_id());
+            assert_eq!(MessageState::Speaking, client.message_status(&21));
+            client.quit().unwrap().check_status(OK_BYE).unwrap();
+            Ok(())
+        }
+    )
+}
+
 #[test]
 #[cfg(not(feature = "async-mio"))]
 fn history_clients_list() -> ClientResult<()> {
@@ -0,0 +1,247 @@
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(all(unix, feature = "tokio"))]
+
+use futures_util::StreamExt;
+use ssip_client_async::{fifo::asynchronous_tokio::Builder, *};
+
+#[allow(dead_code)]
+mod server;
+
+const SET_CLIENT_COMMUNICATION: (&str, &str) = (
+    "SET self CLIENT_NAME test:test:main\r\n",
+    "208 OK CLIENT NAME SET\r\n",
+);
+
+#[test]
+fn send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<
+        tokio::AsyncClient<
+            ::tokio::io::BufReader<::tokio::net::unix::OwnedReadHalf>,
+            ::tokio::io::BufWriter<::tokio::net::unix::OwnedWriteHalf>,
+        >,
+    >();
+    fn assert_send<T: Send>() {}
+    assert_send::<tokio::SpeechHandle<'static>>();
+}
+
+/// Generic code running on any asynchronous client.
+async fn say_and_quit<C: AsyncSsipClient>(client: &mut C) -> ClientResult<MessageId> {
+    client
+        .set_client_name(ClientName::new("test", "test"))
+        .await?;
+    client
+        .set_rate(ClientScope::Current, Rate::try_from(-50).unwrap())
+        .await?;
+    let id = client.say_line(".hidden\nline").await?;
+    client.stop(MessageScope::Message(id)).await?;
+    client.quit().await?;
+    Ok(id)
+}
+
+#[test]
+fn generic_client() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 6] = [
+        SET_CLIENT_COMMUNICATION,
+        ("SET self RATE -50\r\n", "203 OK RATE SET\r\n"),
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        (
+            "..hidden\r\nline\r\n.\r\n",
+            "225-21\r\n225 OK MESSAGE QUEUED\r\n",
+        ),
+        ("STOP 21\r\n", "210 OK STOPPED\r\n"),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("generic.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let rt = ::tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()?;
+    rt.block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        assert_eq!(21, say_and_quit(&mut client).await?);
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn event_stream() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        (
+            "SET self NOTIFICATION all on\r\n",
+            "220 OK NOTIFICATION SET\r\n701-21\r\n701-test\r\n701 BEGIN\r\n702-21\r\n702-test\r\n702 END\r\n",
+        ),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let runtime = ::tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()?;
+    let events = runtime.block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client
+            .set_client_name(ClientName::new("test", "test"))
+            .await?
+            .check_client_name_set()
+            .await?
+            .set_notification(NotificationType::All, true)
+            .await?
+            .check_status(OK_NOTIFICATION_SET)
+            .await?;
+        let mut events = Vec::new();
+        let mut stream = Box::pin(client.event_stream());
+        for _ in 0..2 {
+            events.push(stream.next().await.unwrap()?);
+        }
+        drop(stream);
+        client.quit().await?;
+        // The answer to QUIT ends the stream.
+        let mut stream = Box::pin(client.event_stream());
+        assert!(stream.next().await.is_none());
+        Ok::<_, ClientError>(events)
+    })?;
+    assert_eq!(
+        vec![Event::begin("21", "test"), Event::end("21", "test")],
+        events
+    );
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn say_line_awaitable() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        (
+            "Hello\r\n.\r\n",
+            "225-21\r\n225 OK MESSAGE QUEUED\r\n702-20\r\n702-test\r\n702 END\r\n701-21\r\n701-test\r\n701 BEGIN\r\n703-21\r\n703-test\r\n703 CANCELED\r\n",
+        ),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("test_client.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let runtime = ::tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()?;
+    let (id, ntype) = runtime.block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client
+            .set_client_name(ClientName::new("test", "test"))
+            .await?
+            .check_client_name_set()
+            .await?;
+        let speech = client.say_line_awaitable("Hello").await?;
+        let id = speech.message_id();
+        Ok::<_, ClientError>((id, speech.await?))
+    })?;
+    assert_eq!(21, id);
+    assert_eq!(EventType::Cancel, ntype);
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn send_escaped_lines() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        SET_CLIENT_COMMUNICATION,
+        ("SPEAK\r\n", "230 OK RECEIVING DATA\r\n"),
+        ("..x\r\n.\r\n", "225-21\r\n225 OK MESSAGE QUEUED\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("escape.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let rt = ::tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()?;
+    rt.block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client
+            .set_client_name(ClientName::new("test", "test"))
+            .await?
+            .check_client_name_set()
+            .await?;
+        client.speak().await?.check_receiving_data().await?;
+        client.send_lines(&[String::from(".x")]).await?;
+        assert_eq!(21, client.receive_message_id().await?);
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn keepalive_waits_for_replies() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 3] = [
+        ("SET self RATE 10\r\n", "203 OK RATE SET\r\n"),
+        ("GET RATE\r\n", "251-10\r\n251 OK GET RETURNED\r\n"),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("keepalive.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let rt = ::tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()?;
+    rt.block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client.set_keepalive(Some(std::time::Duration::ZERO));
+        client
+            .send(Request::SetRate(
+                ClientScope::Current,
+                Rate::try_from(10).unwrap(),
+            ))
+            .await?;
+        // The reply to SET RATE is still awaited.
+        assert!(!client.keepalive().await?);
+        client.check_status(OK_RATE_SET).await?;
+        assert!(client.keepalive().await?);
+        client.quit().await?.check_status(OK_BYE).await?;
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn wait_keepalive() -> ClientResult<()> {
+    const COMMUNICATION: [(&str, &str); 2] = [
+        ("GET RATE\r\n", "251-10\r\n251 OK GET RETURNED\r\n"),
+        ("QUIT\r\n", "231 HAPPY HACKING\r\n"),
+    ];
+    let socket_dir = tempfile::tempdir()?;
+    let socket_path = socket_dir.path().join("wait_keepalive.socket");
+    let handle = server::run_unix(&socket_path, &COMMUNICATION)?;
+    let rt = ::tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .enable_time()
+        .build()?;
+    rt.block_on(async {
+        let mut client = Builder::default().path(&socket_path).build().await?;
+        client.set_keepalive(Some(std::time::Duration::from_millis(10)));
+        client.wait_keepalive().await?;
+        client.quit().await?.check_status(OK_BYE).await?;
+        Ok::<(), ClientError>(())
+    })?;
+    handle.join().unwrap().unwrap();
+    socket_dir.close()?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+// Copyright (c) 2022 Laurent Pelecq
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#![cfg(feature = "ws")]
+
+use std::net::TcpListener;
+
+use ssip_client_async::*;
+use tungstenite::Message;
+
+/// Questions as frames and answers sent as one frame per line.
+const COMMUNICATION: [(&[&str], &[&str]); 4] = [
+    (
+        &["SET self CLIENT_NAME test:test:main"],
+        &["208 OK CLIENT NAME SET"],
+    ),
+    (&["SPEAK"], &["230 OK RECEIVING DATA"]),
+    (&["Hello", "."], &["225-21", "225 OK MESSAGE QUEUED"]),
+    (&["QUIT"], &["231 HAPPY HACKING"]),
+];
+
+#[test]
+fn websocket_client() -> ClientResult<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("ws://{}", listener.local_addr()?);
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        for (questions, answers) in COMMUNICATION.iter() {
+            for question in questions.iter() {
+                assert_eq!(Message::text(*question), socket.read().unwrap());
+            }
+            for answer in answers.iter() {
+                socket.send(Message::text(*answer)).unwrap();
+            }
+        }
+    });
+    let mut client = ws::connect(&url)?;
+    client.send(Request::SetName(ClientName::new("test", "test")))?;
+    assert_eq!(Response::ClientNameSet, client.receive()?);
+    client.send(Request::Speak)?;
+    assert_eq!(Response::ReceivingData, client.receive()?);
+    client.send(Request::SendLine(String::from("Hello")))?;
+    assert_eq!(Response::MessageQueued, client.receive()?);
+    client.close()?;
+    handle.join().unwrap();
+    Ok(())
+}
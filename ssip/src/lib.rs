@@ -24,7 +24,7 @@ pub type MessageId = u32;
 pub type ClientId = u32;
 
 /// Message identifiers
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MessageScope {
     /// Last message from current client
     Last,
@@ -44,8 +44,21 @@ impl fmt::Display for MessageScope {
     }
 }
 
+impl From<MessageId> for MessageScope {
+    fn from(id: MessageId) -> Self {
+        MessageScope::Message(id)
+    }
+}
+
+/// The unit value stands for the default scope, the last message.
+impl From<()> for MessageScope {
+    fn from(_: ()) -> Self {
+        MessageScope::Last
+    }
+}
+
 /// Client identifiers
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum ClientScope {
     /// Current client
     Current,
@@ -65,8 +78,107 @@ impl fmt::Display for ClientScope {
     }
 }
 
-/// Priority
-#[derive(StrumDisplay, Debug, Clone, PartialEq, Eq, Hash)]
+impl From<ClientId> for ClientScope {
+    fn from(id: ClientId) -> Self {
+        ClientScope::Client(id)
+    }
+}
+
+/// The unit value stands for the default scope, the current client.
+impl From<()> for ClientScope {
+    fn from(_: ()) -> Self {
+        ClientScope::Current
+    }
+}
+
+/// Declare a speech parameter bound to the range from -100 to 100.
+macro_rules! speech_parameter {
+    ($(#[$meta:meta])* $name:ident, $label:expr, $default:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(try_from = "i8", into = "i8"))]
+        #[cfg_attr(feature = "dbus", derive(zvariant::Type))]
+        pub struct $name(i8);
+
+        impl $name {
+            /// Lowest accepted value.
+            pub const MIN: $name = $name(-100);
+            /// Highest accepted value.
+            pub const MAX: $name = $name(100);
+
+            /// Value sent to the server.
+            pub fn value(&self) -> i8 {
+                self.0
+            }
+        }
+
+        impl Default for $name {
+            /// Default value of Speech Dispatcher.
+            fn default() -> Self {
+                $name($default)
+            }
+        }
+
+        impl TryFrom<i8> for $name {
+            type Error = ClientError;
+
+            fn try_from(value: i8) -> Result<Self, Self::Error> {
+                if (-100..=100).contains(&value) {
+                    Ok($name(value))
+                } else {
+                    Err(ClientError::io_error(
+                        io::ErrorKind::InvalidInput,
+                        concat!($label, " out of range -100..=100"),
+                    ))
+                }
+            }
+        }
+
+        impl From<$name> for i8 {
+            fn from(value: $name) -> i8 {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+
+speech_parameter!(
+    /// Rate of speech, from -100 to 100. Lower values mean slower speech.
+    Rate,
+    "rate",
+    0
+);
+
+speech_parameter!(
+    /// Pitch of speech, from -100 to 100.
+    Pitch,
+    "pitch",
+    0
+);
+
+speech_parameter!(
+    /// Range of the pitch variations, from -100 to 100.
+    PitchRange,
+    "pitch range",
+    0
+);
+
+speech_parameter!(
+    /// Volume of speech, from -100 to 100.
+    Volume,
+    "volume",
+    100
+);
+
+/// Priority, the server default is [`Priority::Text`].
+#[derive(StrumDisplay, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "dbus", derive(zvariant::Type))]
 pub enum Priority {
@@ -76,14 +188,50 @@ pub enum Priority {
     Notification,
     #[strum(serialize = "message")]
     Message,
+    #[default]
     #[strum(serialize = "text")]
     Text,
     #[strum(serialize = "important")]
     Important,
 }
 
+impl Priority {
+    /// Precedence of the priority, higher wins.
+    fn precedence(self) -> u8 {
+        match self {
+            Priority::Notification => 0,
+            Priority::Progress => 1,
+            Priority::Text => 2,
+            Priority::Message => 3,
+            Priority::Important => 4,
+        }
+    }
+}
+
+/// Priorities are ordered by precedence in Speech Dispatcher, from lowest to highest:
+///
+/// * [`Priority::Notification`] is dropped when any other message is spoken or queued.
+/// * [`Priority::Progress`] is dropped like a notification, except that the last message of
+///   a sequence is always spoken.
+/// * [`Priority::Text`] waits for messages and important messages and is canceled by them.
+/// * [`Priority::Message`] waits for important messages but is never dropped.
+/// * [`Priority::Important`] is spoken immediately and never interrupted.
+///
+/// The declaration order of the variants doesn't reflect this precedence.
+impl Ord for Priority {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.precedence().cmp(&other.precedence())
+    }
+}
+
+impl PartialOrd for Priority {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Punctuation mode.
-#[derive(StrumDisplay, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(StrumDisplay, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "dbus", derive(zvariant::Type))]
 pub enum PunctuationMode {
@@ -97,8 +245,22 @@ pub enum PunctuationMode {
     All,
 }
 
+impl FromStr for PunctuationMode {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(PunctuationMode::None),
+            "some" => Ok(PunctuationMode::Some),
+            "most" => Ok(PunctuationMode::Most),
+            "all" => Ok(PunctuationMode::All),
+            _ => Err(ClientError::invalid_data("invalid punctuation mode")),
+        }
+    }
+}
+
 /// Capital letters recognition mode.
-#[derive(StrumDisplay, Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(StrumDisplay, Debug, Clone, Copy, Hash, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "dbus", derive(zvariant::Type))]
 pub enum CapitalLettersRecognitionMode {
@@ -111,7 +273,7 @@ pub enum CapitalLettersRecognitionMode {
 }
 
 /// Symbolic key names
-#[derive(StrumDisplay, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(StrumDisplay, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "dbus", derive(zvariant::Type))]
 pub enum KeyName {
@@ -260,7 +422,7 @@ pub enum KeyName {
 }
 
 /// Notification type
-#[derive(StrumDisplay, Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(StrumDisplay, Debug, Clone, Copy, Hash, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "dbus", derive(zvariant::Type))]
 pub enum NotificationType {
@@ -281,7 +443,7 @@ pub enum NotificationType {
 }
 
 /// Notification event type (returned by server)
-#[derive(StrumDisplay, Debug, Clone)]
+#[derive(StrumDisplay, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum EventType {
     Begin,
     End,
@@ -291,6 +453,48 @@ pub enum EventType {
     IndexMark(String),
 }
 
+impl EventType {
+    /// Event type of an SSIP event code, the payload is the name of an index mark.
+    ///
+    /// Return `None` if the code is not an event or if the index mark has no name.
+    pub fn from_code(code: ReturnCode, payload: Option<&str>) -> Option<EventType> {
+        match code {
+            700 => payload.map(|mark| EventType::IndexMark(mark.to_string())),
+            701 => Some(EventType::Begin),
+            702 => Some(EventType::End),
+            703 => Some(EventType::Cancel),
+            704 => Some(EventType::Pause),
+            705 => Some(EventType::Resume),
+            _ => None,
+        }
+    }
+
+    /// SSIP code of the event.
+    pub fn code(&self) -> ReturnCode {
+        match self {
+            EventType::IndexMark(_) => 700,
+            EventType::Begin => 701,
+            EventType::End => 702,
+            EventType::Cancel => 703,
+            EventType::Pause => 704,
+            EventType::Resume => 705,
+        }
+    }
+}
+
+/// Progress of a message, as known by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageState {
+    /// Message queued by the server, not spoken yet
+    Queued,
+    /// Message being spoken
+    Speaking,
+    /// Message spoken or canceled
+    Finished,
+    /// State can't be determined
+    Unknown,
+}
+
 /// Event identifier
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -313,7 +517,7 @@ impl EventId {
 }
 
 /// Notification event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Event {
     pub ntype: EventType,
     pub id: EventId,
@@ -350,10 +554,68 @@ impl Event {
     pub fn resume(message: &str, client: &str) -> Event {
         Event::new(EventType::Resume, message, client)
     }
+
+    /// Return true if the event is about the message, including index marks.
+    pub fn matches(&self, id: &MessageId) -> bool {
+        self.id.message.parse::<MessageId>() == Ok(*id)
+    }
+}
+
+/// Speech parameters of a client
+///
+/// A field is `None` when the value is unknown or, when applying settings, left unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settings {
+    pub rate: Option<Rate>,
+    pub pitch: Option<Pitch>,
+    pub volume: Option<Volume>,
+    /// Symbolic voice name such as `MALE1`.
+    pub voice: Option<String>,
+    pub language: Option<String>,
+    pub punctuation: Option<PunctuationMode>,
+}
+
+/// Speech profile
+///
+/// Bundle the client name, the speech parameters and the synthesis voice to switch
+/// between configurations, for example reading and proofreading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    pub name: ClientName,
+    pub settings: Settings,
+    pub voice: SynthesisVoice,
+}
+
+impl Profile {
+    pub fn new(name: ClientName, settings: Settings, voice: SynthesisVoice) -> Self {
+        Profile {
+            name,
+            settings,
+            voice,
+        }
+    }
+
+    /// Check the client name with [`ClientName::validate`] and that the voice name is not
+    /// empty.
+    ///
+    /// Speech parameters are always in range, deserializing an out of range value fails.
+    pub fn validate(&self) -> ClientResult<()> {
+        self.name.validate()?;
+        if self.voice.name.trim().is_empty() {
+            return Err(ClientError::io_error(
+                io::ErrorKind::InvalidInput,
+                "empty voice name",
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Synthesis voice
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SynthesisVoice {
     pub name: String,
     pub language: Option<String>,
@@ -380,12 +642,42 @@ impl SynthesisVoice {
     }
 }
 
+/// Format the voice as in `LIST SYNTHESIS_VOICES`, missing fields are `none`.
+impl fmt::Display for SynthesisVoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}",
+            self.name,
+            self.language.as_deref().unwrap_or("none"),
+            self.dialect.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+/// Voices are sorted by language, name and dialect.
+impl Ord for SynthesisVoice {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.language, &self.name, &self.dialect).cmp(&(
+            &other.language,
+            &other.name,
+            &other.dialect,
+        ))
+    }
+}
+
+impl PartialOrd for SynthesisVoice {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl FromStr for SynthesisVoice {
     type Err = ClientError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split('\t');
-        match iter.next() {
+        let mut iter = s.trim_end_matches('\r').split('\t');
+        match iter.next().filter(|name| !name.is_empty()) {
             Some(name) => Ok(SynthesisVoice {
                 name: name.to_string(),
                 language: SynthesisVoice::parse_none(iter.next()),
@@ -411,26 +703,79 @@ pub struct StatusLine {
     pub message: String,
 }
 
+/// Answer of the server to `SET self CLIENT_NAME`
+///
+/// Besides the status, some servers send the id assigned to the connection, either on a
+/// data line (`208-42`) or at the end of the message (`208 OK CLIENT NAME SET 42`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Handshake {
+    pub code: ReturnCode,
+    pub message: String,
+    pub client_id: Option<ClientId>,
+}
+
 impl fmt::Display for StatusLine {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} {}", self.code, self.message)
     }
 }
 /// Client error, either I/O error or SSIP error.
+///
+/// Errors are `Send` and `Sync` so they can be returned from threads and boxed as `dyn Error`.
 #[derive(ThisError, Debug)]
 pub enum ClientError {
     #[error("I/O: {0}")]
     Io(io::Error),
     #[error("Not ready")]
     NotReady,
+    #[error("Disconnected by the server")]
+    Disconnected,
+    #[error("Timed out")]
+    Timeout,
     #[error("SSIP: {0}")]
     Ssip(StatusLine),
-    #[error("Too few lines")]
-    TooFewLines,
-    #[error("Too many lines")]
-    TooManyLines,
+    #[error("Too few lines: expected {expected}, got {got}")]
+    TooFewLines { expected: usize, got: usize },
+    #[error("Too many lines: expected {expected}, got {got}")]
+    TooManyLines { expected: usize, got: usize },
     #[error("Unexpected status: {0}")]
     UnexpectedStatus(ReturnCode),
+    #[error("Invalid type: {0}")]
+    InvalidType(String),
+    #[error("Batch interrupted after {} messages: {source}", ids.len())]
+    PartialBatch {
+        /// Identifiers of the messages queued before the failure.
+        ids: Vec<MessageId>,
+        source: Box<ClientError>,
+    },
+    #[error("Request {index} of pipeline failed: {source}")]
+    PipelineFailed {
+        /// Position of the first failed request.
+        index: usize,
+        source: Box<ClientError>,
+    },
+    #[error("Unknown id {id}: {status}")]
+    UnknownId {
+        /// Message or client id targeted by the request.
+        id: u32,
+        status: StatusLine,
+    },
+    #[error("Notifications not supported: {0}")]
+    NotificationsUnsupported(StatusLine),
+    #[error("Failed to apply settings: {}", failed_settings(.failures))]
+    SettingsFailed {
+        /// Name of each setting that failed with its error.
+        failures: Vec<(&'static str, ClientError)>,
+    },
+}
+
+fn failed_settings(failures: &[(&'static str, ClientError)]) -> String {
+    failures
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl ClientError {
@@ -448,18 +793,39 @@ impl ClientError {
     pub fn unexpected_eof(msg: &str) -> Self {
         ClientError::io_error(io::ErrorKind::UnexpectedEof, msg)
     }
+
+    /// Return true if the same request may succeed when sent again.
+    ///
+    /// Only the server errors for a full queue (`431`) or a missing output module (`321`)
+    /// are transient. Other server errors are permanent for the same request.
+    ///
+    /// I/O errors, including timeouts and non-blocking reads that are not ready, are not
+    /// retryable: the request may have been written while its answer is still unread, so
+    /// sending it again would mix up the answers.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Ssip(status) => matches!(status.code, 321 | 431),
+            _ => false,
+        }
+    }
 }
 
 impl From<io::Error> for ClientError {
     fn from(err: io::Error) -> Self {
-        if err.kind() == io::ErrorKind::WouldBlock {
-            ClientError::NotReady
-        } else {
-            ClientError::Io(err)
+        match err.kind() {
+            io::ErrorKind::WouldBlock => ClientError::NotReady,
+            io::ErrorKind::TimedOut => ClientError::Timeout,
+            _ => ClientError::Io(err),
         }
     }
 }
 
+impl From<std::convert::Infallible> for ClientError {
+    fn from(err: std::convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
 /// Client result.
 pub type ClientResult<T> = Result<T, ClientError>;
 
@@ -488,6 +854,53 @@ impl ClientName {
             component: component.to_string(),
         }
     }
+
+    /// Client name of the current process
+    ///
+    /// The user is read from `$USER` or `$LOGNAME`, the application is the name of the
+    /// executable and the component is `main`. Placeholders are used when they are not
+    /// available, so the result is always valid.
+    pub fn from_env() -> Self {
+        let user = ["USER", "LOGNAME"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_else(|| String::from("unknown"));
+        let application = std::env::current_exe()
+            .ok()
+            .and_then(|path| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| String::from("unknown"));
+        ClientName::new(
+            &ClientName::sanitize(&user),
+            &ClientName::sanitize(&application),
+        )
+    }
+
+    /// Replace the characters that are not allowed in a field
+    fn sanitize(field: &str) -> String {
+        field.replace([':', '\r', '\n'], "_")
+    }
+
+    /// Check that the fields can be sent in `SET self CLIENT_NAME user:application:component`
+    ///
+    /// Fields must not contain `:` that separates them nor line breaks that end the command.
+    pub fn validate(&self) -> ClientResult<()> {
+        for (label, field) in [
+            ("user", &self.user),
+            ("application", &self.application),
+            ("component", &self.component),
+        ] {
+            if field.contains([':', '\r', '\n']) {
+                return Err(ClientError::io_error(
+                    io::ErrorKind::InvalidInput,
+                    &format!("invalid character in client {} name", label),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Cursor motion in history
@@ -608,120 +1021,345 @@ impl FromStr for HistoryClientStatus {
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 /// Request for SSIP server.
+///
+/// Requests only own plain data and are `Send` and `Sync`. New commands may be added,
+/// so matches outside this crate need a wildcard arm.
+#[non_exhaustive]
 pub enum Request {
+    /// `SET self CLIENT_NAME user:application:component`, must be sent first.
     SetName(ClientName),
     // Speech related requests
+    /// `SPEAK`, the server then expects the text of the message.
     Speak,
+    /// One line of text after `SPEAK`, followed by the terminating dot.
     SendLine(String),
+    /// Several lines of text after `SPEAK`, followed by the terminating dot.
     SendLines(Vec<String>),
+    /// `CHAR <char>` speaks a single character.
     SpeakChar(char),
+    /// `KEY <key>` speaks a key name.
     SpeakKey(KeyName),
     // Flow control
+    /// `STOP <scope>` stops the message being spoken, a number is a message id.
     Stop(MessageScope),
+    /// `CANCEL <scope>` stops and discards the queued messages, a number is a message id.
     Cancel(MessageScope),
+    /// `PAUSE <scope>` pauses speech, a number is a message id.
     Pause(MessageScope),
+    /// `RESUME <scope>` resumes paused speech, a number is a message id.
     Resume(MessageScope),
+    /// `STOP <scope>` on the messages of the clients in scope, a number is a client id.
+    StopClient(ClientScope),
+    /// `CANCEL <scope>` on the messages of the clients in scope, a number is a client id.
+    CancelClient(ClientScope),
+    /// `PAUSE <scope>` on the messages of the clients in scope, a number is a client id.
+    PauseClient(ClientScope),
+    /// `RESUME <scope>` on the messages of the clients in scope, a number is a client id.
+    ResumeClient(ClientScope),
     // Setter and getter
+    /// `SET self PRIORITY <priority>` for the next messages.
     SetPriority(Priority),
+    /// `SET all DEBUG on|off` toggles the debug logs of the server.
     SetDebug(bool),
+    /// `SET <scope> OUTPUT_MODULE <name>`
     SetOutputModule(ClientScope, String),
+    /// `GET OUTPUT_MODULE`
     GetOutputModule,
+    /// `LIST OUTPUT_MODULES`
     ListOutputModules,
+    /// `SET <scope> LANGUAGE <code>`
     SetLanguage(ClientScope, String),
+    /// `GET LANGUAGE`
     GetLanguage,
+    /// `SET self SSML_MODE on|off`
     SetSsmlMode(bool),
+    /// `SET <scope> PUNCTUATION <mode>`
     SetPunctuationMode(ClientScope, PunctuationMode),
+    /// `GET PUNCTUATION`, refused by servers that can't report it.
+    GetPunctuationMode,
+    /// `SET <scope> SPELLING on|off`
     SetSpelling(ClientScope, bool),
+    /// `SET <scope> CAP_LET_RECOGN <mode>`
     SetCapitalLettersRecognitionMode(ClientScope, CapitalLettersRecognitionMode),
+    /// `SET <scope> VOICE_TYPE <type>` selects a symbolic voice.
     SetVoiceType(ClientScope, String),
+    /// `GET VOICE_TYPE`
     GetVoiceType,
+    /// `LIST VOICES` lists the symbolic voices.
     ListVoiceTypes,
+    /// `SET <scope> SYNTHESIS_VOICE <name>`
     SetSynthesisVoice(ClientScope, String),
+    /// `LIST SYNTHESIS_VOICES` lists the voices of the output module.
     ListSynthesisVoices,
-    SetRate(ClientScope, i8),
+    /// `SET <scope> RATE <value>`
+    SetRate(ClientScope, Rate),
+    /// `GET RATE`
     GetRate,
-    SetPitch(ClientScope, i8),
+    /// `SET <scope> PITCH <value>`
+    SetPitch(ClientScope, Pitch),
+    /// `GET PITCH`
     GetPitch,
-    SetVolume(ClientScope, i8),
+    /// `SET <scope> PITCH_RANGE <value>`
+    SetPitchRange(ClientScope, PitchRange),
+    /// `GET PITCH_RANGE`
+    GetPitchRange,
+    /// `SET <scope> VOLUME <value>`
+    SetVolume(ClientScope, Volume),
+    /// `GET VOLUME`
     GetVolume,
+    /// `SET <scope> PAUSE_CONTEXT <value>`
     SetPauseContext(ClientScope, u32),
+    /// `SET self NOTIFICATION <type> on|off` enables events.
     SetNotification(NotificationType, bool),
     // Blocks
+    /// `BLOCK BEGIN` opens a block of messages spoken together.
     Begin,
+    /// `BLOCK END` closes the block.
     End,
     // History
+    /// `SET <scope> HISTORY on|off`
     SetHistory(ClientScope, bool),
+    /// `HISTORY GET CLIENT_LIST`
     HistoryGetClients,
+    /// `HISTORY GET CLIENT_ID`
     HistoryGetClientId,
+    /// `HISTORY GET CLIENT_MESSAGES <scope> <start>_<number>`
     HistoryGetClientMsgs(ClientScope, u32, u32),
+    /// `HISTORY GET LAST`
     HistoryGetLastMsgId,
+    /// `HISTORY GET MESSAGE <id>`
     HistoryGetMsg(MessageId),
+    /// `HISTORY CURSOR GET`
     HistoryCursorGet,
+    /// `HISTORY CURSOR SET <scope> <position>`
     HistoryCursorSet(ClientScope, HistoryPosition),
+    /// `HISTORY CURSOR <direction>`
     HistoryCursorMove(CursorDirection),
+    /// `HISTORY SAY <id>` speaks a message again.
     HistorySpeak(MessageId),
+    /// `HISTORY SORT <direction> <key>`
     HistorySort(SortDirection, SortKey),
+    /// `HISTORY SET SHORT_MESSAGE_LENGTH <length>`
     HistorySetShortMsgLength(u32),
+    /// `HISTORY SET MESSAGE_TYPE_ORDERING "<ordering>"`
     HistorySetMsgTypeOrdering(Vec<Ordering>),
+    /// `HISTORY SEARCH <scope> "<condition>"`
     HistorySearch(ClientScope, String),
     // Misc.
+    /// `HELP` lists the commands understood by the server.
+    Help,
+    /// `QUIT` closes the connection.
     Quit,
 }
 
+impl Request {
+    /// Command keyword sent on the wire, such as `SET`, `SPEAK` or `HISTORY`.
+    ///
+    /// The text lines sent after `SPEAK` are not commands, their verb is empty.
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Request::SendLine(_) | Request::SendLines(_) => "",
+            Request::Speak => "SPEAK",
+            Request::SpeakChar(_) => "CHAR",
+            Request::SpeakKey(_) => "KEY",
+            Request::Stop(_) | Request::StopClient(_) => "STOP",
+            Request::Cancel(_) | Request::CancelClient(_) => "CANCEL",
+            Request::Pause(_) | Request::PauseClient(_) => "PAUSE",
+            Request::Resume(_) | Request::ResumeClient(_) => "RESUME",
+            Request::SetName(_)
+            | Request::SetPriority(_)
+            | Request::SetDebug(_)
+            | Request::SetOutputModule(..)
+            | Request::SetLanguage(..)
+            | Request::SetSsmlMode(_)
+            | Request::SetPunctuationMode(..)
+            | Request::SetSpelling(..)
+            | Request::SetCapitalLettersRecognitionMode(..)
+            | Request::SetVoiceType(..)
+            | Request::SetSynthesisVoice(..)
+            | Request::SetRate(..)
+            | Request::SetPitch(..)
+            | Request::SetPitchRange(..)
+            | Request::SetVolume(..)
+            | Request::SetPauseContext(..)
+            | Request::SetNotification(..)
+            | Request::SetHistory(..) => "SET",
+            Request::GetOutputModule
+            | Request::GetLanguage
+            | Request::GetPunctuationMode
+            | Request::GetVoiceType
+            | Request::GetRate
+            | Request::GetPitch
+            | Request::GetPitchRange
+            | Request::GetVolume => "GET",
+            Request::ListOutputModules | Request::ListVoiceTypes | Request::ListSynthesisVoices => {
+                "LIST"
+            }
+            Request::Begin | Request::End => "BLOCK",
+            Request::HistoryGetClients
+            | Request::HistoryGetClientId
+            | Request::HistoryGetClientMsgs(..)
+            | Request::HistoryGetLastMsgId
+            | Request::HistoryGetMsg(_)
+            | Request::HistoryCursorGet
+            | Request::HistoryCursorSet(..)
+            | Request::HistoryCursorMove(_)
+            | Request::HistorySpeak(_)
+            | Request::HistorySort(..)
+            | Request::HistorySetShortMsgLength(_)
+            | Request::HistorySetMsgTypeOrdering(_)
+            | Request::HistorySearch(..) => "HISTORY",
+            Request::Help => "HELP",
+            Request::Quit => "QUIT",
+        }
+    }
+
+    /// Clients targeted by the request, if it has a client scope.
+    pub fn scope(&self) -> Option<&ClientScope> {
+        match self {
+            Request::StopClient(scope)
+            | Request::CancelClient(scope)
+            | Request::PauseClient(scope)
+            | Request::ResumeClient(scope) => Some(scope),
+            Request::SetOutputModule(scope, _)
+            | Request::SetLanguage(scope, _)
+            | Request::SetPunctuationMode(scope, _)
+            | Request::SetSpelling(scope, _)
+            | Request::SetCapitalLettersRecognitionMode(scope, _)
+            | Request::SetVoiceType(scope, _)
+            | Request::SetSynthesisVoice(scope, _)
+            | Request::SetRate(scope, _)
+            | Request::SetPitch(scope, _)
+            | Request::SetPitchRange(scope, _)
+            | Request::SetVolume(scope, _)
+            | Request::SetPauseContext(scope, _)
+            | Request::SetHistory(scope, _)
+            | Request::HistoryGetClientMsgs(scope, _, _)
+            | Request::HistoryCursorSet(scope, _)
+            | Request::HistorySearch(scope, _) => Some(scope),
+            _ => None,
+        }
+    }
+
+    /// Messages targeted by the request, if it has a message scope.
+    pub fn message_scope(&self) -> Option<&MessageScope> {
+        match self {
+            Request::Stop(scope)
+            | Request::Cancel(scope)
+            | Request::Pause(scope)
+            | Request::Resume(scope) => Some(scope),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 /// Response from SSIP server.
+///
+/// Responses only own plain data and are `Send` and `Sync`.
 pub enum Response {
-    LanguageSet,                                     // 201
-    PrioritySet,                                     // 202
-    RateSet,                                         // 203
-    PitchSet,                                        // 204
-    PunctuationSet,                                  // 205
-    CapLetRecognSet,                                 // 206
-    SpellingSet,                                     // 207
-    ClientNameSet,                                   // 208
-    VoiceSet,                                        // 209
-    Stopped,                                         // 210
-    Paused,                                          // 211
-    Resumed,                                         // 212
-    Canceled,                                        // 213
-    TableSet,                                        // 215
-    OutputModuleSet,                                 // 216
-    PauseContextSet,                                 // 217
-    VolumeSet,                                       // 218
-    SsmlModeSet,                                     // 219
-    NotificationSet,                                 // 220
-    PitchRangeSet,                                   // 263
-    DebugSet,                                        // 262
-    HistoryCurSetFirst,                              // 220
-    HistoryCurSetLast,                               // 221
-    HistoryCurSetPos,                                // 222
-    HistoryCurMoveFor,                               // 223
-    HistoryCurMoveBack,                              // 224
-    MessageQueued,                                   // 225,
-    SoundIconQueued,                                 // 226
-    MessageCanceled,                                 // 227
-    ReceivingData,                                   // 230
-    Bye,                                             // 231
-    HistoryClientListSent(Vec<HistoryClientStatus>), // 240
-    HistoryMsgsListSent(Vec<String>),                // 241
-    HistoryLastMsg(String),                          // 242
-    HistoryCurPosRet(String),                        // 243
-    TableListSent(Vec<String>),                      // 244
-    HistoryClientIdSent(ClientId),                   // 245
-    MessageTextSent,                                 // 246
-    HelpSent(Vec<String>),                           // 248
-    VoicesListSent(Vec<SynthesisVoice>),             // 249
-    OutputModulesListSent(Vec<String>),              // 250
-    Get(String),                                     // 251
-    InsideBlock,                                     // 260
-    OutsideBlock,                                    // 261
-    NotImplemented,                                  // 299
-    EventIndexMark(EventId, String),                 // 700
-    EventBegin(EventId),                             // 701
-    EventEnd(EventId),                               // 702
-    EventCanceled(EventId),                          // 703
-    EventPaused(EventId),                            // 704
-    EventResumed(EventId),                           // 705
+    /// Language set (201)
+    LanguageSet,
+    /// Priority set (202)
+    PrioritySet,
+    /// Rate set (203)
+    RateSet,
+    /// Pitch set (204)
+    PitchSet,
+    /// Punctuation mode set (205)
+    PunctuationSet,
+    /// Capital letters recognition mode set (206)
+    CapLetRecognSet,
+    /// Spelling mode set (207)
+    SpellingSet,
+    /// Client name set (208)
+    ClientNameSet,
+    /// Voice set (209)
+    VoiceSet,
+    /// Message stopped (210)
+    Stopped,
+    /// Message paused (211)
+    Paused,
+    /// Message resumed (212)
+    Resumed,
+    /// Messages canceled (213)
+    Canceled,
+    /// Table set (215)
+    TableSet,
+    /// Output module set (216)
+    OutputModuleSet,
+    /// Pause context set (217)
+    PauseContextSet,
+    /// Volume set (218)
+    VolumeSet,
+    /// SSML mode set (219)
+    SsmlModeSet,
+    /// Notification set (220)
+    NotificationSet,
+    /// Pitch range set (263)
+    PitchRangeSet,
+    /// Debug mode set (262)
+    DebugSet,
+    /// History cursor set to the first message (220)
+    HistoryCurSetFirst,
+    /// History cursor set to the last message (221)
+    HistoryCurSetLast,
+    /// History cursor set to a position (222)
+    HistoryCurSetPos,
+    /// History cursor moved forward (223)
+    HistoryCurMoveFor,
+    /// History cursor moved backward (224)
+    HistoryCurMoveBack,
+    /// Message queued, its id is not kept (225)
+    MessageQueued,
+    /// Sound icon queued (226)
+    SoundIconQueued,
+    /// Message canceled (227)
+    MessageCanceled,
+    /// The server is waiting for the text after `SPEAK` (230)
+    ReceivingData,
+    /// Answer to `QUIT` (231)
+    Bye,
+    /// Clients in the history (240)
+    HistoryClientListSent(Vec<HistoryClientStatus>),
+    /// Messages in the history (241)
+    HistoryMsgsListSent(Vec<String>),
+    /// Last message in the history (242)
+    HistoryLastMsg(String),
+    /// Position of the history cursor (243)
+    HistoryCurPosRet(String),
+    /// List of tables (244)
+    TableListSent(Vec<String>),
+    /// Id of the current client (245)
+    HistoryClientIdSent(ClientId),
+    /// Text of a message from the history (246)
+    MessageTextSent,
+    /// Help lines (248)
+    HelpSent(Vec<String>),
+    /// Synthesis voices (249)
+    VoicesListSent(Vec<SynthesisVoice>),
+    /// Output modules (250)
+    OutputModulesListSent(Vec<String>),
+    /// Value returned by a `GET` request (251)
+    Get(String),
+    /// Block opened (260)
+    InsideBlock,
+    /// Block closed (261)
+    OutsideBlock,
+    /// Command not implemented by the server (299)
+    NotImplemented,
+    /// Event: index mark reached, with the name of the mark (700)
+    EventIndexMark(EventId, String),
+    /// Event: message started (701)
+    EventBegin(EventId),
+    /// Event: message finished (702)
+    EventEnd(EventId),
+    /// Event: message canceled (703)
+    EventCanceled(EventId),
+    /// Event: message paused (704)
+    EventPaused(EventId),
+    /// Event: message resumed (705)
+    EventResumed(EventId),
 }
 
 #[cfg(test)]
@@ -730,7 +1368,78 @@ mod tests {
     use std::io;
     use std::str::FromStr;
 
-    use super::{ClientError, HistoryClientStatus, HistoryPosition, MessageScope, SynthesisVoice};
+    use super::{
+        ClientError, ClientName, ClientScope, EventType, HistoryClientStatus, HistoryPosition,
+        MessageScope, Pitch, PitchRange, PunctuationMode, Rate, Request, StatusLine,
+        SynthesisVoice, Volume,
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn priority_precedence() {
+        use super::Priority;
+        let mut priorities = vec![
+            Priority::Important,
+            Priority::Text,
+            Priority::Progress,
+            Priority::Message,
+            Priority::Notification,
+        ];
+        priorities.sort();
+        assert_eq!(
+            vec![
+                Priority::Notification,
+                Priority::Progress,
+                Priority::Text,
+                Priority::Message,
+                Priority::Important
+            ],
+            priorities
+        );
+        assert!(Priority::Important > Priority::Message);
+        assert_eq!(Priority::Text, Priority::Text.max(Priority::Progress));
+    }
+
+    #[test]
+    fn send_and_sync() {
+        assert_send_sync::<Request>();
+        assert_send_sync::<super::Response>();
+        assert_send_sync::<ClientError>();
+        assert_send_sync::<StatusLine>();
+        assert_send_sync::<super::Event>();
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn profile_round_trip() {
+        use super::{Profile, PunctuationMode, Settings};
+
+        let profile = Profile::new(
+            ClientName::new("joe", "reader"),
+            Settings {
+                rate: Some(Rate::try_from(-20).unwrap()),
+                volume: Some(Volume::try_from(80).unwrap()),
+                punctuation: Some(PunctuationMode::Most),
+                ..Settings::default()
+            },
+            SynthesisVoice::new("lancashire", Some("en"), None),
+        );
+        assert!(profile.validate().is_ok());
+        let json = serde_json::to_string(&profile).unwrap();
+        assert_eq!(profile, serde_json::from_str::<Profile>(&json).unwrap());
+        let text = toml::to_string(&profile).unwrap();
+        assert_eq!(profile, toml::from_str::<Profile>(&text).unwrap());
+
+        let mut invalid = profile.clone();
+        invalid.voice.name = String::from(" ");
+        assert!(invalid.validate().is_err());
+        let mut invalid = profile.clone();
+        invalid.name = ClientName::new("joe", "reader:proofreader");
+        assert!(invalid.validate().is_err());
+        let json = json.replace("-20", "-120");
+        assert!(serde_json::from_str::<Profile>(&json).is_err());
+    }
 
     #[test]
     fn parse_synthesis_voice() {
@@ -746,6 +1455,177 @@ mod tests {
         assert_eq!("Esperanto", v2.name);
         assert_eq!("eo", v2.language.unwrap());
         assert!(v2.dialect.is_none());
+
+        // Trailing carriage return
+        let v3 = SynthesisVoice::from_str("Esperanto\teo\tnone\r").unwrap();
+        assert!(v3.dialect.is_none());
+
+        // Voice name only
+        let v4 = SynthesisVoice::from_str("Esperanto").unwrap();
+        assert_eq!("Esperanto", v4.name);
+        assert!(v4.language.is_none());
+        assert!(v4.dialect.is_none());
+
+        // No voice name
+        for line in &["", "\r", "\teo\tnone"] {
+            match SynthesisVoice::from_str(line) {
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof => (),
+                _ => panic!("expecting error 'unexpected EOF' parsing {:?}", line),
+            }
+        }
+    }
+
+    #[test]
+    fn format_synthesis_voice() {
+        for voice in [
+            SynthesisVoice::new(
+                "Portuguese (Portugal)+Kaukovalta",
+                Some("pt"),
+                Some("Kaukovalta"),
+            ),
+            SynthesisVoice::new("Esperanto", Some("eo"), None),
+            SynthesisVoice::new("Unknown", None, None),
+        ] {
+            assert_eq!(voice, SynthesisVoice::from_str(&voice.to_string()).unwrap());
+        }
+        assert_eq!(
+            "Esperanto\teo\tnone",
+            SynthesisVoice::new("Esperanto", Some("eo"), None).to_string()
+        );
+    }
+
+    #[test]
+    fn sort_synthesis_voices() {
+        let mut voices = vec![
+            SynthesisVoice::new("Esperanto", Some("eo"), None),
+            SynthesisVoice::new("English (America)", Some("en"), Some("us")),
+            SynthesisVoice::new("English (Great Britain)", Some("en"), None),
+            SynthesisVoice::new("English (America)", Some("en"), None),
+        ];
+        voices.sort();
+        assert_eq!(
+            vec![
+                SynthesisVoice::new("English (America)", Some("en"), None),
+                SynthesisVoice::new("English (America)", Some("en"), Some("us")),
+                SynthesisVoice::new("English (Great Britain)", Some("en"), None),
+                SynthesisVoice::new("Esperanto", Some("eo"), None),
+            ],
+            voices
+        );
+        let unique = voices
+            .iter()
+            .chain(voices.iter())
+            .cloned()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(voices.len(), unique.len());
+    }
+
+    #[test]
+    fn validate_client_name() {
+        assert!(ClientName::with_component("joe", "speechd_client", "main")
+            .validate()
+            .is_ok());
+        for name in [
+            ClientName::new("joe", "app:other"),
+            ClientName::new("joe\r\nQUIT", "app"),
+            ClientName::with_component("joe", "app", "main\n"),
+        ] {
+            match name.validate() {
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidInput => (),
+                _ => panic!("expecting error 'invalid input' for {:?}", name),
+            }
+        }
+    }
+
+    #[test]
+    fn client_name_from_env() {
+        let name = ClientName::from_env();
+        assert!(!name.user.is_empty());
+        assert!(!name.application.is_empty());
+        assert_eq!("main", name.component);
+        assert!(name.validate().is_ok());
+    }
+
+    #[test]
+    fn parse_punctuation_mode() {
+        for mode in [
+            PunctuationMode::None,
+            PunctuationMode::Some,
+            PunctuationMode::Most,
+            PunctuationMode::All,
+        ] {
+            assert_eq!(mode, PunctuationMode::from_str(&mode.to_string()).unwrap());
+        }
+        assert!(PunctuationMode::from_str("every").is_err());
+    }
+
+    #[test]
+    fn request_verb_and_scope() {
+        let set_rate = Request::SetRate(ClientScope::All, Rate::default());
+        assert_eq!("SET", set_rate.verb());
+        assert_eq!(Some(&ClientScope::All), set_rate.scope());
+        assert_eq!(None, set_rate.message_scope());
+        let stop = Request::Stop(MessageScope::Last);
+        assert_eq!("STOP", stop.verb());
+        assert_eq!(None, stop.scope());
+        assert_eq!(Some(&MessageScope::Last), stop.message_scope());
+        let stop_client = Request::StopClient(ClientScope::Client(7));
+        assert_eq!("STOP", stop_client.verb());
+        assert_eq!(Some(&ClientScope::Client(7)), stop_client.scope());
+        assert_eq!(None, stop_client.message_scope());
+        assert_eq!("HISTORY", Request::HistoryGetClients.verb());
+        assert_eq!("HELP", Request::Help.verb());
+        assert_eq!("", Request::SendLine(String::from("hello")).verb());
+    }
+
+    #[test]
+    fn scope_from_id() {
+        let message: MessageScope = 12.into();
+        assert_eq!(MessageScope::Message(12), message);
+        let client: ClientScope = 3.into();
+        assert_eq!(ClientScope::Client(3), client);
+    }
+
+    #[test]
+    fn event_type_code() {
+        for ntype in [
+            EventType::IndexMark(String::from("mark")),
+            EventType::Begin,
+            EventType::End,
+            EventType::Cancel,
+            EventType::Pause,
+            EventType::Resume,
+        ] {
+            let payload = match &ntype {
+                EventType::IndexMark(mark) => Some(mark.as_str()),
+                _ => None,
+            };
+            assert_eq!(
+                Some(ntype.clone()),
+                EventType::from_code(ntype.code(), payload)
+            );
+        }
+        assert_eq!(None, EventType::from_code(700, None));
+        assert_eq!(None, EventType::from_code(210, None));
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let status = |code| {
+            ClientError::Ssip(StatusLine {
+                code,
+                message: String::from("ERR"),
+            })
+        };
+        assert!(status(431).is_retryable());
+        assert!(status(321).is_retryable());
+        assert!(!status(409).is_retryable());
+        assert!(!status(410).is_retryable());
+        assert!(!status(510).is_retryable());
+        assert!(!ClientError::Timeout.is_retryable());
+        assert!(!ClientError::NotReady.is_retryable());
+        assert!(!ClientError::from(io::Error::from(io::ErrorKind::Interrupted)).is_retryable());
+        assert!(!ClientError::invalid_data("bad").is_retryable());
     }
 
     #[test]
@@ -755,6 +1635,52 @@ mod tests {
         assert_eq!("123", format!("{}", MessageScope::Message(123)).as_str());
     }
 
+    #[test]
+    fn speech_parameter_range() {
+        assert_eq!(-100, Rate::try_from(-100).unwrap().value());
+        assert_eq!("100", Volume::try_from(100).unwrap().to_string());
+        for value in [-128, -101, 101, 127] {
+            match Rate::try_from(value) {
+                Err(ClientError::Io(err)) if err.kind() == io::ErrorKind::InvalidInput => (),
+                _ => panic!("expecting error 'invalid input' for {}", value),
+            }
+            assert!(Pitch::try_from(value).is_err());
+            assert!(PitchRange::try_from(value).is_err());
+            assert!(Volume::try_from(value).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserialize_speech_parameter() {
+        assert_eq!(
+            "-20",
+            serde_json::to_string(&Rate::try_from(-20).unwrap()).unwrap()
+        );
+        assert_eq!(
+            Pitch::try_from(100).unwrap(),
+            serde_json::from_str::<Pitch>("100").unwrap()
+        );
+        for json in ["101", "-101"] {
+            assert!(serde_json::from_str::<Rate>(json).is_err());
+            assert!(serde_json::from_str::<Pitch>(json).is_err());
+            assert!(serde_json::from_str::<Volume>(json).is_err());
+        }
+    }
+
+    #[test]
+    fn convert_io_error() {
+        let would_block = io::Error::from(io::ErrorKind::WouldBlock);
+        assert!(matches!(
+            ClientError::from(would_block),
+            ClientError::NotReady
+        ));
+        let timed_out = io::Error::from(io::ErrorKind::TimedOut);
+        assert!(matches!(ClientError::from(timed_out), ClientError::Timeout));
+        let other = io::Error::from(io::ErrorKind::BrokenPipe);
+        assert!(matches!(ClientError::from(other), ClientError::Io(_)));
+    }
+
     #[test]
     fn format_history_position() {
         assert_eq!("first", format!("{}", HistoryPosition::First).as_str());